@@ -0,0 +1,113 @@
+//! Cross-program invocations of the [lido] program.
+
+use anchor_lang::{prelude::*, solana_program};
+
+/// Accounts for the [lido] CPIs.
+pub mod accounts {
+    use super::*;
+
+    /// Accounts for [super::deposit].
+    #[derive(Accounts)]
+    pub struct Deposit<'info> {
+        /// The Solido account.
+        #[account(mut)]
+        pub lido: AccountInfo<'info>,
+        /// Account the SOL is transferred from.
+        #[account(mut, signer)]
+        pub user: AccountInfo<'info>,
+        /// stSOL token account receiving the minted stSOL.
+        #[account(mut)]
+        pub recipient: AccountInfo<'info>,
+        /// The stSOL mint.
+        #[account(mut)]
+        pub st_sol_mint: AccountInfo<'info>,
+        /// Reserve of deposited SOL.
+        #[account(mut)]
+        pub reserve_account: AccountInfo<'info>,
+        /// Mint authority of the stSOL mint.
+        pub mint_authority: AccountInfo<'info>,
+        /// Token program.
+        pub spl_token: AccountInfo<'info>,
+        /// System program.
+        pub system_program: AccountInfo<'info>,
+    }
+
+    /// Accounts for [super::withdraw].
+    #[derive(Accounts)]
+    pub struct Withdraw<'info> {
+        /// The Solido account.
+        #[account(mut)]
+        pub lido: AccountInfo<'info>,
+        /// Owner of the stSOL token account.
+        #[account(signer)]
+        pub st_sol_account_owner: AccountInfo<'info>,
+        /// stSOL token account the stSOL is burned from.
+        #[account(mut)]
+        pub st_sol_account: AccountInfo<'info>,
+        /// The stSOL mint.
+        #[account(mut)]
+        pub st_sol_mint: AccountInfo<'info>,
+        /// Vote account of the validator to withdraw from.
+        pub validator_vote_account: AccountInfo<'info>,
+        /// Validator stake account the stake is split from.
+        /// See [crate::Lido::find_withdraw_source_stake_account].
+        #[account(mut)]
+        pub source_stake_account: AccountInfo<'info>,
+        /// Uninitialized stake account receiving the withdrawn stake.
+        /// Its staker and withdrawer become the [Withdraw::st_sol_account_owner].
+        #[account(mut, signer)]
+        pub destination_stake_account: AccountInfo<'info>,
+        /// Stake authority of the Solido account.
+        pub stake_authority: AccountInfo<'info>,
+        /// Token program.
+        pub spl_token: AccountInfo<'info>,
+        /// Clock sysvar.
+        pub sysvar_clock: AccountInfo<'info>,
+        /// System program.
+        pub system_program: AccountInfo<'info>,
+        /// Stake program.
+        pub stake_program: AccountInfo<'info>,
+    }
+}
+
+/// Deposits SOL into Solido in exchange for stSOL.
+pub fn deposit<'a, 'b, 'c, 'info>(
+    ctx: CpiContext<'a, 'b, 'c, 'info, accounts::Deposit<'info>>,
+    amount: u64,
+) -> ProgramResult {
+    let ix = lido::instruction::deposit(
+        ctx.program.key,
+        &lido::instruction::DepositAccountsMeta {
+            lido: ctx.accounts.lido.key(),
+            user: ctx.accounts.user.key(),
+            recipient: ctx.accounts.recipient.key(),
+            st_sol_mint: ctx.accounts.st_sol_mint.key(),
+            reserve_account: ctx.accounts.reserve_account.key(),
+            mint_authority: ctx.accounts.mint_authority.key(),
+        },
+        lido::token::Lamports(amount),
+    );
+    solana_program::program::invoke_signed(&ix, &ctx.to_account_infos(), ctx.signer_seeds)
+}
+
+/// Withdraws stSOL from Solido in exchange for a stake account.
+pub fn withdraw<'a, 'b, 'c, 'info>(
+    ctx: CpiContext<'a, 'b, 'c, 'info, accounts::Withdraw<'info>>,
+    amount: u64,
+) -> ProgramResult {
+    let ix = lido::instruction::withdraw(
+        ctx.program.key,
+        &lido::instruction::WithdrawAccountsMeta {
+            lido: ctx.accounts.lido.key(),
+            st_sol_account_owner: ctx.accounts.st_sol_account_owner.key(),
+            st_sol_account: ctx.accounts.st_sol_account.key(),
+            st_sol_mint: ctx.accounts.st_sol_mint.key(),
+            validator_vote_account: ctx.accounts.validator_vote_account.key(),
+            source_stake_account: ctx.accounts.source_stake_account.key(),
+            destination_stake_account: ctx.accounts.destination_stake_account.key(),
+            stake_authority: ctx.accounts.stake_authority.key(),
+        },
+        lido::token::StLamports(amount),
+    );
+    solana_program::program::invoke_signed(&ix, &ctx.to_account_infos(), ctx.signer_seeds)
+}
@@ -3,6 +3,9 @@ use std::ops::Deref;
 
 use anchor_lang::prelude::*;
 
+#[cfg(feature = "cpi")]
+pub mod cpi;
+
 declare_id!("CrX7kMhLC3cSsXJdT7JDgqrRVWGnUpX3gfEfxxU2NVLi");
 
 mod solido_account {
@@ -14,9 +17,49 @@ mod solido_account {
 /// Solido account
 pub static SOLIDO_ACCOUNT: Pubkey = solido_account::ID;
 
+/// Finds the address of an authority of a Solido account, e.g. [lido::RESERVE_ACCOUNT],
+/// [lido::MINT_AUTHORITY] or [lido::STAKE_AUTHORITY].
+pub fn find_authority_address(lido: &Pubkey, authority: &[u8]) -> (Pubkey, u8) {
+    lido::find_authority_program_address(&crate::ID, lido, authority)
+}
+
+/// Finds the address of the active stake account of a validator at the given seed.
+pub fn find_validator_stake_account(
+    lido: &Pubkey,
+    validator_vote_account: &Pubkey,
+    seed: u64,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            lido.as_ref(),
+            validator_vote_account.as_ref(),
+            lido::VALIDATOR_STAKE_ACCOUNT,
+            &seed.to_le_bytes(),
+        ],
+        &crate::ID,
+    )
+}
+
 #[derive(Clone, Debug, Default)]
 pub struct Lido(lido::state::Lido);
 
+impl Lido {
+    /// Finds the validator stake account that a withdrawal from the validator is split from.
+    /// This is the oldest active stake account of the validator.
+    pub fn find_withdraw_source_stake_account(
+        &self,
+        lido: &Pubkey,
+        validator_vote_account: &Pubkey,
+    ) -> Result<(Pubkey, u8), ProgramError> {
+        let validator = self.validators.get(validator_vote_account)?;
+        Ok(find_validator_stake_account(
+            lido,
+            validator_vote_account,
+            validator.entry.stake_seeds.begin,
+        ))
+    }
+}
+
 impl Owner for Lido {
     fn owner() -> Pubkey {
         crate::ID
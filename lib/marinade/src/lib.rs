@@ -11,11 +11,43 @@ pub mod main_state {
     declare_id!("8szGkuLTAux9XMgZ2vtY39jVSowEcpBfFfD8hXSEqdGC");
 }
 
+/// Mirror of the Marinade program interface.
+///
+/// The instruction handlers only exist to generate the CPI client.
+/// They are never executed by Marinade and fail with [ProgramError::IncorrectProgramId] if invoked.
 #[program]
-#[allow(deprecated)]
+#[allow(deprecated, unused_variables)]
 pub mod marinade {
     use super::*;
 
+    /// Deposits SOL in exchange for mSOL.
+    pub fn deposit(ctx: Context<Deposit>, lamports: u64) -> ProgramResult {
+        Err(ProgramError::IncorrectProgramId)
+    }
+
+    /// Deposits a stake account in exchange for mSOL.
+    pub fn deposit_stake_account(
+        ctx: Context<DepositStakeAccount>,
+        validator_index: u32,
+    ) -> ProgramResult {
+        Err(ProgramError::IncorrectProgramId)
+    }
+
+    /// Swaps mSOL for SOL via the liquidity pool.
+    pub fn liquid_unstake(ctx: Context<LiquidUnstake>, msol_amount: u64) -> ProgramResult {
+        Err(ProgramError::IncorrectProgramId)
+    }
+
+    /// Burns mSOL in exchange for a [TicketAccountData], claimable for SOL once the stake is cooled down.
+    pub fn order_unstake(ctx: Context<OrderUnstake>, msol_amount: u64) -> ProgramResult {
+        Err(ProgramError::IncorrectProgramId)
+    }
+
+    /// Claims the SOL of a [TicketAccountData].
+    pub fn claim(ctx: Context<Claim>) -> ProgramResult {
+        Err(ProgramError::IncorrectProgramId)
+    }
+
     #[state]
     #[derive(Default)]
     pub struct State {
@@ -43,6 +75,178 @@ pub mod marinade {
     }
 }
 
+/// Accounts for [marinade::deposit].
+#[derive(Accounts)]
+pub struct Deposit<'info> {
+    /// The Marinade [State].
+    #[account(mut)]
+    pub state: AccountInfo<'info>,
+    /// [State::msol_mint].
+    #[account(mut)]
+    pub msol_mint: AccountInfo<'info>,
+    /// SOL leg of the liquidity pool.
+    #[account(mut)]
+    pub liq_pool_sol_leg_pda: AccountInfo<'info>,
+    /// mSOL leg of the liquidity pool.
+    #[account(mut)]
+    pub liq_pool_msol_leg: AccountInfo<'info>,
+    /// Authority of the mSOL leg of the liquidity pool.
+    pub liq_pool_msol_leg_authority: AccountInfo<'info>,
+    /// Reserve of deposited SOL.
+    #[account(mut)]
+    pub reserve_pda: AccountInfo<'info>,
+    /// Account the SOL is transferred from.
+    #[account(mut, signer)]
+    pub transfer_from: AccountInfo<'info>,
+    /// mSOL token account receiving the minted mSOL.
+    #[account(mut)]
+    pub mint_to: AccountInfo<'info>,
+    /// Mint authority of [State::msol_mint].
+    pub msol_mint_authority: AccountInfo<'info>,
+    /// System program.
+    pub system_program: AccountInfo<'info>,
+    /// Token program.
+    pub token_program: AccountInfo<'info>,
+}
+
+/// Accounts for [marinade::deposit_stake_account].
+#[derive(Accounts)]
+pub struct DepositStakeAccount<'info> {
+    /// The Marinade [State].
+    #[account(mut)]
+    pub state: AccountInfo<'info>,
+    /// [ValidatorSystem::validator_list].
+    #[account(mut)]
+    pub validator_list: AccountInfo<'info>,
+    /// [StakeSystem::stake_list].
+    #[account(mut)]
+    pub stake_list: AccountInfo<'info>,
+    /// Stake account to deposit.
+    #[account(mut)]
+    pub stake_account: AccountInfo<'info>,
+    /// Staker and withdrawer of the stake account.
+    #[account(signer)]
+    pub stake_authority: AccountInfo<'info>,
+    /// Flag preventing the same stake account from being deposited twice.
+    #[account(mut)]
+    pub duplication_flag: AccountInfo<'info>,
+    /// Payer of the rent of the duplication flag.
+    #[account(mut, signer)]
+    pub rent_payer: AccountInfo<'info>,
+    /// [State::msol_mint].
+    #[account(mut)]
+    pub msol_mint: AccountInfo<'info>,
+    /// mSOL token account receiving the minted mSOL.
+    #[account(mut)]
+    pub mint_to: AccountInfo<'info>,
+    /// Mint authority of [State::msol_mint].
+    pub msol_mint_authority: AccountInfo<'info>,
+    /// Clock sysvar.
+    pub clock: AccountInfo<'info>,
+    /// Rent sysvar.
+    pub rent: AccountInfo<'info>,
+    /// System program.
+    pub system_program: AccountInfo<'info>,
+    /// Token program.
+    pub token_program: AccountInfo<'info>,
+    /// Stake program.
+    pub stake_program: AccountInfo<'info>,
+}
+
+/// Accounts for [marinade::liquid_unstake].
+#[derive(Accounts)]
+pub struct LiquidUnstake<'info> {
+    /// The Marinade [State].
+    #[account(mut)]
+    pub state: AccountInfo<'info>,
+    /// [State::msol_mint].
+    #[account(mut)]
+    pub msol_mint: AccountInfo<'info>,
+    /// SOL leg of the liquidity pool.
+    #[account(mut)]
+    pub liq_pool_sol_leg_pda: AccountInfo<'info>,
+    /// mSOL leg of the liquidity pool.
+    #[account(mut)]
+    pub liq_pool_msol_leg: AccountInfo<'info>,
+    /// [State::treasury_msol_account].
+    #[account(mut)]
+    pub treasury_msol_account: AccountInfo<'info>,
+    /// mSOL token account the mSOL is transferred from.
+    #[account(mut)]
+    pub get_msol_from: AccountInfo<'info>,
+    /// Authority of the mSOL token account.
+    #[account(signer)]
+    pub get_msol_from_authority: AccountInfo<'info>,
+    /// Account receiving the SOL.
+    #[account(mut)]
+    pub transfer_sol_to: AccountInfo<'info>,
+    /// System program.
+    pub system_program: AccountInfo<'info>,
+    /// Token program.
+    pub token_program: AccountInfo<'info>,
+}
+
+/// Accounts for [marinade::order_unstake].
+#[derive(Accounts)]
+pub struct OrderUnstake<'info> {
+    /// The Marinade [State].
+    #[account(mut)]
+    pub state: AccountInfo<'info>,
+    /// [State::msol_mint].
+    #[account(mut)]
+    pub msol_mint: AccountInfo<'info>,
+    /// mSOL token account the mSOL is burned from.
+    #[account(mut)]
+    pub burn_msol_from: AccountInfo<'info>,
+    /// Authority of the mSOL token account.
+    #[account(signer)]
+    pub burn_msol_authority: AccountInfo<'info>,
+    /// Uninitialized, rent-exempt [TicketAccountData] account owned by Marinade.
+    #[account(mut)]
+    pub new_ticket_account: AccountInfo<'info>,
+    /// Clock sysvar.
+    pub clock: AccountInfo<'info>,
+    /// Rent sysvar.
+    pub rent: AccountInfo<'info>,
+    /// Token program.
+    pub token_program: AccountInfo<'info>,
+}
+
+/// Accounts for [marinade::claim].
+#[derive(Accounts)]
+pub struct Claim<'info> {
+    /// The Marinade [State].
+    #[account(mut)]
+    pub state: AccountInfo<'info>,
+    /// Reserve of deposited SOL.
+    #[account(mut)]
+    pub reserve_pda: AccountInfo<'info>,
+    /// The [TicketAccountData] to claim.
+    #[account(mut)]
+    pub ticket_account: AccountInfo<'info>,
+    /// [TicketAccountData::beneficiary], which receives the SOL.
+    #[account(mut)]
+    pub transfer_sol_to: AccountInfo<'info>,
+    /// Clock sysvar.
+    pub clock: AccountInfo<'info>,
+    /// System program.
+    pub system_program: AccountInfo<'info>,
+}
+
+/// A delayed unstake ticket, created by [marinade::order_unstake].
+#[account]
+#[derive(Default)]
+pub struct TicketAccountData {
+    /// The Marinade [State].
+    pub state_address: Pubkey,
+    /// Account that may claim the SOL.
+    pub beneficiary: Pubkey,
+    /// Amount of SOL claimable.
+    pub lamports_amount: u64,
+    /// Epoch the ticket was created in. The ticket may be claimed once the stake has cooled down.
+    pub created_epoch: u64,
+}
+
 #[account]
 #[derive(Default)]
 pub struct State {
@@ -69,6 +273,65 @@ pub struct State {
     pub emergency_cooling_down: u64,
 }
 
+impl State {
+    /// Seed of [State::reserve_address].
+    pub const RESERVE_SEED: &'static [u8] = b"reserve";
+    /// Seed of [State::msol_mint_authority].
+    pub const MSOL_MINT_AUTHORITY_SEED: &'static [u8] = b"st_mint";
+
+    /// Address of the reserve of deposited SOL.
+    pub fn reserve_address(&self, state: &Pubkey) -> Result<Pubkey, ProgramError> {
+        create_state_address(state, Self::RESERVE_SEED, self.reserve_bump_seed)
+    }
+
+    /// Mint authority of [State::msol_mint].
+    pub fn msol_mint_authority(&self, state: &Pubkey) -> Result<Pubkey, ProgramError> {
+        create_state_address(
+            state,
+            Self::MSOL_MINT_AUTHORITY_SEED,
+            self.msol_mint_authority_bump_seed,
+        )
+    }
+}
+
+impl LiqPool {
+    /// Seed of [LiqPool::sol_leg_address].
+    pub const SOL_LEG_SEED: &'static [u8] = b"liq_sol";
+    /// Seed of [LiqPool::msol_leg_authority].
+    pub const MSOL_LEG_AUTHORITY_SEED: &'static [u8] = b"liq_st_sol_authority";
+    /// Seed of [LiqPool::lp_mint_authority].
+    pub const LP_MINT_AUTHORITY_SEED: &'static [u8] = b"liq_mint";
+
+    /// Address of the SOL leg of the liquidity pool.
+    pub fn sol_leg_address(&self, state: &Pubkey) -> Result<Pubkey, ProgramError> {
+        create_state_address(state, Self::SOL_LEG_SEED, self.sol_leg_bump_seed)
+    }
+
+    /// Authority of [LiqPool::msol_leg].
+    pub fn msol_leg_authority(&self, state: &Pubkey) -> Result<Pubkey, ProgramError> {
+        create_state_address(
+            state,
+            Self::MSOL_LEG_AUTHORITY_SEED,
+            self.msol_leg_authority_bump_seed,
+        )
+    }
+
+    /// Mint authority of [LiqPool::lp_mint].
+    pub fn lp_mint_authority(&self, state: &Pubkey) -> Result<Pubkey, ProgramError> {
+        create_state_address(
+            state,
+            Self::LP_MINT_AUTHORITY_SEED,
+            self.lp_mint_authority_bump_seed,
+        )
+    }
+}
+
+/// Creates a program address derived from the Marinade [State].
+fn create_state_address(state: &Pubkey, seed: &[u8], bump: u8) -> Result<Pubkey, ProgramError> {
+    Pubkey::create_program_address(&[state.as_ref(), seed, &[bump]], &crate::ID)
+        .map_err(|_| ProgramError::InvalidSeeds)
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize, Default, Debug, Clone, Copy)]
 pub struct Fee {
     pub basis_points: u32,
@@ -0,0 +1,67 @@
+//! Cross-program invocations of the SPL stake pool program.
+
+use anchor_lang::{prelude::*, solana_program};
+
+/// Index of the `DepositStake` instruction of the stake pool program.
+const DEPOSIT_STAKE_INSTRUCTION: u8 = 9;
+
+/// Accounts for the stake pool CPIs.
+pub mod accounts {
+    use super::*;
+
+    /// Accounts for [super::deposit_stake].
+    #[derive(Accounts)]
+    pub struct DepositStake<'info> {
+        /// The [crate::StakePool].
+        #[account(mut)]
+        pub stake_pool: AccountInfo<'info>,
+        /// [crate::StakePool::validator_list].
+        #[account(mut)]
+        pub validator_list: AccountInfo<'info>,
+        /// Stake deposit authority of the pool.
+        pub deposit_authority: AccountInfo<'info>,
+        /// Stake withdraw authority of the pool.
+        pub withdraw_authority: AccountInfo<'info>,
+        /// Stake account to deposit. Its authorities must already be the deposit authority.
+        #[account(mut)]
+        pub stake: AccountInfo<'info>,
+        /// Validator stake account the deposited stake is merged into.
+        #[account(mut)]
+        pub validator_stake: AccountInfo<'info>,
+        /// [crate::StakePool::reserve_stake].
+        #[account(mut)]
+        pub reserve_stake: AccountInfo<'info>,
+        /// Token account receiving the pool tokens.
+        #[account(mut)]
+        pub pool_tokens_to: AccountInfo<'info>,
+        /// [crate::StakePool::manager_fee_account].
+        #[account(mut)]
+        pub manager_fee_account: AccountInfo<'info>,
+        /// Token account receiving the referral fee.
+        #[account(mut)]
+        pub referrer_pool_tokens_account: AccountInfo<'info>,
+        /// [crate::StakePool::pool_mint].
+        #[account(mut)]
+        pub pool_mint: AccountInfo<'info>,
+        /// Clock sysvar.
+        pub clock: AccountInfo<'info>,
+        /// Stake history sysvar.
+        pub stake_history: AccountInfo<'info>,
+        /// Token program.
+        pub token_program: AccountInfo<'info>,
+        /// Stake program.
+        pub stake_program: AccountInfo<'info>,
+    }
+}
+
+/// Deposits a stake account into the stake pool in exchange for pool tokens.
+pub fn deposit_stake<'a, 'b, 'c, 'info>(
+    ctx: CpiContext<'a, 'b, 'c, 'info, accounts::DepositStake<'info>>,
+) -> ProgramResult {
+    let ix = solana_program::instruction::Instruction {
+        program_id: *ctx.program.key,
+        accounts: ctx.accounts.to_account_metas(None),
+        data: vec![DEPOSIT_STAKE_INSTRUCTION],
+    };
+    solana_program::program::invoke_signed(&ix, &ctx.to_account_infos(), ctx.signer_seeds)
+}
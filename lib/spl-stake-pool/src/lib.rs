@@ -0,0 +1,81 @@
+//! Anchor client for the [SPL stake pool program](https://github.com/solana-labs/solana-program-library/tree/master/stake-pool).
+use anchor_lang::prelude::*;
+
+#[cfg(feature = "cpi")]
+pub mod cpi;
+
+// Address of the canonical deployment. Stake pools may be run by other deployments of the program,
+// so [StakePool] does not implement [Owner] and callers must check the owner of the account.
+declare_id!("SPoo1Ku8WFXoNDMHPsrGSTSG1Y47rzgn41SLUNakuHy");
+
+/// Type of an account owned by the stake pool program.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AccountType {
+    /// If the account has not been initialized, the enum will be 0.
+    #[default]
+    Uninitialized,
+    /// Stake pool.
+    StakePool,
+    /// Validator stake list.
+    ValidatorList,
+}
+
+/// Stake pool account.
+///
+/// Only the fixed-size prefix of the account is deserialized;
+/// the fee configuration that follows it is ignored.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default)]
+pub struct StakePool {
+    pub account_type: AccountType,
+    pub manager: Pubkey,
+    pub staker: Pubkey,
+    pub stake_deposit_authority: Pubkey,
+    pub stake_withdraw_bump_seed: u8,
+    pub validator_list: Pubkey,
+    pub reserve_stake: Pubkey,
+    pub pool_mint: Pubkey,
+    pub manager_fee_account: Pubkey,
+    pub token_program_id: Pubkey,
+    /// Total stake under management.
+    pub total_lamports: u64,
+    /// Total supply of pool tokens.
+    pub pool_token_supply: u64,
+    /// Last epoch the `total_lamports` field was updated.
+    pub last_update_epoch: u64,
+    pub lockup: Lockup,
+    pub epoch_fee: Fee,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Default, Debug, Clone, Copy)]
+pub struct Lockup {
+    pub unix_timestamp: i64,
+    pub epoch: u64,
+    pub custodian: Pubkey,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Default, Debug, Clone, Copy)]
+pub struct Fee {
+    pub denominator: u64,
+    pub numerator: u64,
+}
+
+impl AccountSerialize for StakePool {
+    fn try_serialize<W: std::io::Write>(&self, writer: &mut W) -> Result<(), ProgramError> {
+        AnchorSerialize::serialize(self, writer).map_err(|_| ProgramError::InvalidAccountData)
+    }
+}
+
+impl AccountDeserialize for StakePool {
+    fn try_deserialize(buf: &mut &[u8]) -> Result<Self, ProgramError> {
+        let result = Self::try_deserialize_unchecked(buf)?;
+        if result.account_type != AccountType::StakePool {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(result)
+    }
+
+    fn try_deserialize_unchecked(buf: &mut &[u8]) -> Result<Self, ProgramError> {
+        let result: StakePool = AnchorDeserialize::deserialize(buf)?;
+        Ok(result)
+    }
+}
@@ -1,21 +1,24 @@
 //! Validate accounts
 
 use anchor_lang::prelude::*;
-use vipers::{assert_keys, invariant};
+use vipers::{assert_ata, assert_keys, invariant, unwrap_or_err};
 
 use crate::{
-    stake_pool_mints, AddStakePool, MintASol, NewAggregate, SetCurator, SyncAll, SyncAndMint,
-    SyncLido, SyncMarinade, LAMPORTS_DECIMALS,
+    stake_pool_mints, AcceptCurator, AcceptCuratorSetter, AccountingMethod, AddStakePool,
+    Aggregate, ClaimPerformanceFee, DepositSol, DepositSolLido, DepositSolMarinade,
+    DepositStakeAccount, DepositStakeMarinade, DepositStakeSplStakePool, GetExchangeRate, MintASol,
+    MintMulti, MintMultiPool, MintReferrer, NewAggregate, NewExchangeRateOracle, NewPosition,
+    NewReferrer, RedeemASol, RedeemASolPool, Refresh, RemoveStakePool, RetireStakePool, SetCurator,
+    SetCuratorSetter, SetFeeDestinations, SetFees, SetGuardian, SetPaused, SetPerformanceFee,
+    SetReferralFee, SetSingleWithdrawFee, SetStakePoolLimits, SetStakePoolPaused, StakePoolMeta,
+    SweepFees, SyncAndMint, SyncAndRedeem, SyncAndWithdrawSingle, SyncLido, SyncMarinade,
+    SyncSplStakePool, SyncStakePool, LAMPORTS_DECIMALS, PAUSE_ADD_STAKE_POOL, PAUSE_MINT,
+    PAUSE_REDEEM,
 };
 use vipers::validate::Validate;
 
 impl<'info> Validate<'info> for NewAggregate<'info> {
     fn validate(&self) -> ProgramResult {
-        assert_keys!(
-            self.redeem_in_kind,
-            crate_redeem_in_kind::WITHDRAW_AUTHORITY_ADDRESS,
-            "redeem_in_kind"
-        );
         invariant!(self.crate_mint.supply == 0, "supply must be zero");
         invariant!(
             self.crate_mint.decimals == LAMPORTS_DECIMALS,
@@ -25,12 +28,38 @@ impl<'info> Validate<'info> for NewAggregate<'info> {
     }
 }
 
+impl<'info> Validate<'info> for NewPosition<'info> {
+    fn validate(&self) -> ProgramResult {
+        Ok(())
+    }
+}
+
+impl<'info> Validate<'info> for NewReferrer<'info> {
+    fn validate(&self) -> ProgramResult {
+        assert_keys!(
+            self.aggregate.crate_token,
+            *self.crate_token,
+            "aggregate.crate_token"
+        );
+        assert_keys!(
+            self.fee_destination.mint,
+            self.crate_token.mint,
+            "fee_destination.mint"
+        );
+        Ok(())
+    }
+}
+
 impl<'info> Validate<'info> for AddStakePool<'info> {
     fn validate(&self) -> ProgramResult {
         require!(
             self.curator.key() == self.aggregate.curator,
             UnauthorizedNotCurator
         );
+        require!(
+            !self.aggregate.is_paused(PAUSE_ADD_STAKE_POOL),
+            AggregatePaused
+        );
         require!(
             !self
                 .aggregate
@@ -43,6 +72,102 @@ impl<'info> Validate<'info> for AddStakePool<'info> {
     }
 }
 
+impl<'info> AddStakePool<'info> {
+    /// Validates that the pool state matches the accounting method.
+    pub fn validate_pool_state(&self, accounting_method: AccountingMethod) -> ProgramResult {
+        match accounting_method {
+            AccountingMethod::Marinade => {
+                assert_keys!(self.pool_state, marinade::main_state::ID, "pool_state");
+            }
+            AccountingMethod::Lido => {
+                assert_keys!(self.pool_state, lido_anchor::SOLIDO_ACCOUNT, "pool_state");
+            }
+            AccountingMethod::SplStakePool => {
+                // any deployment of the SPL stake pool program may run the pool
+                let spl_stake_pool = spl_stake_pool_anchor::StakePool::try_deserialize(
+                    &mut &self.pool_state.try_borrow_data()?[..],
+                )?;
+                assert_keys!(spl_stake_pool.pool_mint, self.mint, "pool_state.pool_mint");
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'info> Validate<'info> for RetireStakePool<'info> {
+    fn validate(&self) -> ProgramResult {
+        require!(
+            self.curator.key() == self.aggregate.curator,
+            UnauthorizedNotCurator
+        );
+        assert_keys!(
+            self.stake_pool.aggregate,
+            self.aggregate,
+            "stake_pool.aggregate"
+        );
+        let pool = unwrap_or_err!(
+            self.aggregate.find_stake_pool(self.stake_pool.mint),
+            PoolNotFoundInSnapshot
+        );
+        require!(!pool.is_retired, StakePoolRetired);
+        Ok(())
+    }
+}
+
+impl<'info> Validate<'info> for RemoveStakePool<'info> {
+    fn validate(&self) -> ProgramResult {
+        require!(
+            self.curator.key() == self.aggregate.curator,
+            UnauthorizedNotCurator
+        );
+        assert_keys!(
+            self.stake_pool.aggregate,
+            self.aggregate,
+            "stake_pool.aggregate"
+        );
+
+        assert_keys!(
+            self.aggregate.crate_token,
+            *self.crate_token,
+            "aggregate.crate_token"
+        );
+        assert_ata!(
+            *self.crate_reserves,
+            *self.crate_token,
+            self.stake_pool.mint,
+            "crate_reserves"
+        );
+        assert_keys!(
+            self.dust_destination.mint,
+            self.stake_pool.mint,
+            "dust_destination.mint"
+        );
+
+        // only tokens deposited through the program must be withdrawn before removal
+        let pool = unwrap_or_err!(
+            self.aggregate.find_stake_pool(self.stake_pool.mint),
+            PoolNotFoundInSnapshot
+        );
+        require!(pool.reserves == 0, StakePoolReservesNotEmpty);
+        Ok(())
+    }
+}
+
+impl<'info> Validate<'info> for SetStakePoolLimits<'info> {
+    fn validate(&self) -> ProgramResult {
+        require!(
+            self.curator.key() == self.aggregate.curator,
+            UnauthorizedNotCurator
+        );
+        assert_keys!(
+            self.stake_pool.aggregate,
+            self.aggregate,
+            "stake_pool.aggregate"
+        );
+        Ok(())
+    }
+}
+
 impl<'info> Validate<'info> for SetCurator<'info> {
     fn validate(&self) -> ProgramResult {
         require!(
@@ -53,14 +178,209 @@ impl<'info> Validate<'info> for SetCurator<'info> {
     }
 }
 
-impl<'info> Validate<'info> for SyncAll<'info> {
+impl<'info> Validate<'info> for AcceptCurator<'info> {
+    fn validate(&self) -> ProgramResult {
+        require!(
+            self.aggregate.pending_curator == self.pending_curator.key(),
+            UnauthorizedNotPendingCurator
+        );
+        Ok(())
+    }
+}
+
+impl<'info> Validate<'info> for SetCuratorSetter<'info> {
+    fn validate(&self) -> ProgramResult {
+        require!(
+            self.aggregate.curator_setter == self.curator_setter.key(),
+            UnauthorizedNotCuratorSetter
+        );
+        Ok(())
+    }
+}
+
+impl<'info> Validate<'info> for AcceptCuratorSetter<'info> {
+    fn validate(&self) -> ProgramResult {
+        require!(
+            self.aggregate.pending_curator_setter == self.pending_curator_setter.key(),
+            UnauthorizedNotPendingCuratorSetter
+        );
+        Ok(())
+    }
+}
+
+impl<'info> Validate<'info> for SetGuardian<'info> {
+    fn validate(&self) -> ProgramResult {
+        require!(
+            self.aggregate.curator_setter == self.curator_setter.key(),
+            UnauthorizedNotCuratorSetter
+        );
+        Ok(())
+    }
+}
+
+impl<'info> Validate<'info> for SetPaused<'info> {
+    fn validate(&self) -> ProgramResult {
+        require!(
+            self.aggregate.guardian == self.guardian.key(),
+            UnauthorizedNotGuardian
+        );
+        Ok(())
+    }
+}
+
+impl<'info> Validate<'info> for SetStakePoolPaused<'info> {
+    fn validate(&self) -> ProgramResult {
+        require!(
+            self.aggregate.guardian == self.guardian.key(),
+            UnauthorizedNotGuardian
+        );
+        assert_keys!(
+            self.stake_pool.aggregate,
+            self.aggregate,
+            "stake_pool.aggregate"
+        );
+        Ok(())
+    }
+}
+
+impl<'info> Validate<'info> for SetFees<'info> {
+    fn validate(&self) -> ProgramResult {
+        require!(
+            self.curator.key() == self.aggregate.curator,
+            UnauthorizedNotCurator
+        );
+        assert_keys!(
+            self.aggregate.crate_token,
+            self.crate_token,
+            "aggregate.crate_token"
+        );
+        Ok(())
+    }
+}
+
+impl<'info> Validate<'info> for SetFeeDestinations<'info> {
+    fn validate(&self) -> ProgramResult {
+        require!(
+            self.curator.key() == self.aggregate.curator,
+            UnauthorizedNotCurator
+        );
+        assert_keys!(
+            self.aggregate.crate_token,
+            self.crate_token,
+            "aggregate.crate_token"
+        );
+        assert_keys!(
+            self.author_fee_destination.mint,
+            self.crate_token.mint,
+            "author_fee_destination.mint"
+        );
+        assert_keys!(
+            self.author_fee_destination.owner,
+            self.crate_token.author_fee_to,
+            "author_fee_destination.owner"
+        );
+        assert_keys!(
+            self.protocol_fee_destination.mint,
+            self.crate_token.mint,
+            "protocol_fee_destination.mint"
+        );
+        assert_keys!(
+            self.protocol_fee_destination.owner,
+            crate_token::FEE_TO_ADDRESS,
+            "protocol_fee_destination.owner"
+        );
+        Ok(())
+    }
+}
+
+impl<'info> Validate<'info> for SweepFees<'info> {
+    fn validate(&self) -> ProgramResult {
+        require!(
+            self.curator.key() == self.aggregate.curator,
+            UnauthorizedNotCurator
+        );
+        assert_keys!(self.fee_source.owner, self.aggregate, "fee_source.owner");
+        assert_keys!(
+            self.fee_recipient.mint,
+            self.fee_source.mint,
+            "fee_recipient.mint"
+        );
+        Ok(())
+    }
+}
+
+impl<'info> Validate<'info> for SetReferralFee<'info> {
     fn validate(&self) -> ProgramResult {
-        self.lido.validate()?;
-        self.marinade.validate()?;
+        require!(
+            self.curator.key() == self.aggregate.curator,
+            UnauthorizedNotCurator
+        );
         Ok(())
     }
 }
 
+impl<'info> Validate<'info> for SetPerformanceFee<'info> {
+    fn validate(&self) -> ProgramResult {
+        require!(
+            self.curator.key() == self.aggregate.curator,
+            UnauthorizedNotCurator
+        );
+        assert_keys!(
+            self.aggregate.crate_token,
+            self.crate_token,
+            "aggregate.crate_token"
+        );
+        assert_keys!(self.treasury.mint, self.crate_token.mint, "treasury.mint");
+        Ok(())
+    }
+}
+
+impl<'info> Validate<'info> for ClaimPerformanceFee<'info> {
+    fn validate(&self) -> ProgramResult {
+        assert_keys!(
+            self.aggregate.crate_token,
+            *self.crate_token,
+            "aggregate.crate_token"
+        );
+        assert_keys!(self.crate_token.mint, *self.crate_mint, "crate_token.mint");
+        assert_keys!(*self.treasury, self.aggregate.treasury, "treasury");
+        // only validate fee destinations if there are fees
+        if self.crate_token.issue_fee_bps != 0 {
+            assert_keys!(
+                *self.author_fee_destination,
+                self.aggregate.author_fee_destination,
+                "author_fee_destination"
+            );
+            assert_keys!(
+                *self.protocol_fee_destination,
+                self.aggregate.protocol_fee_destination,
+                "protocol_fee_destination"
+            );
+        }
+        Ok(())
+    }
+}
+
+impl<'info> Validate<'info> for SetSingleWithdrawFee<'info> {
+    fn validate(&self) -> ProgramResult {
+        require!(
+            self.curator.key() == self.aggregate.curator,
+            UnauthorizedNotCurator
+        );
+        Ok(())
+    }
+}
+
+impl<'info> Validate<'info> for SyncStakePool<'info> {
+    fn validate(&self) -> ProgramResult {
+        match self {
+            SyncStakePool::Marinade(sync) => sync.validate(),
+            SyncStakePool::Lido(sync) => sync.validate(),
+            SyncStakePool::SplStakePool(sync) => sync.validate(),
+        }
+    }
+}
+
 impl<'info> Validate<'info> for SyncLido<'info> {
     fn validate(&self) -> ProgramResult {
         assert_keys!(*self.lido, lido_anchor::SOLIDO_ACCOUNT, "lido");
@@ -77,6 +397,11 @@ impl<'info> Validate<'info> for SyncLido<'info> {
 impl<'info> Validate<'info> for SyncMarinade<'info> {
     fn validate(&self) -> ProgramResult {
         assert_keys!(*self.marinade, marinade::main_state::ID, "marinade");
+        assert_keys!(
+            self.marinade.msol_mint,
+            stake_pool_mints::marinade_msol::ID,
+            "marinade.msol_mint"
+        );
         // redundant since it's already validated by being in the list
         assert_keys!(
             self.marinade_stake_pool_tokens.mint,
@@ -87,6 +412,17 @@ impl<'info> Validate<'info> for SyncMarinade<'info> {
     }
 }
 
+impl<'info> Validate<'info> for SyncSplStakePool<'info> {
+    fn validate(&self) -> ProgramResult {
+        assert_keys!(
+            self.spl_stake_pool_tokens.mint,
+            self.spl_stake_pool_state()?.pool_mint,
+            "spl_stake_pool_tokens.mint"
+        );
+        Ok(())
+    }
+}
+
 impl<'info> Validate<'info> for MintASol<'info> {
     fn validate(&self) -> ProgramResult {
         assert_keys!(
@@ -94,15 +430,23 @@ impl<'info> Validate<'info> for MintASol<'info> {
             self.aggregate,
             "stake_pool.aggregate"
         );
+        let pool = unwrap_or_err!(
+            self.aggregate.find_stake_pool(self.stake_pool.mint),
+            PoolNotFoundInSnapshot
+        );
+        require!(!pool.is_retired, StakePoolRetired);
+        require!(!self.aggregate.is_paused(PAUSE_MINT), AggregatePaused);
+        require!(!self.stake_pool.is_paused(PAUSE_MINT), StakePoolPaused);
         assert_keys!(
             self.stake_pool_tokens.mint,
             self.stake_pool.mint,
             "stake_pool_tokens.mint",
         );
-        assert_keys!(
+        assert_ata!(
+            *self.stake_pool_tokens,
             *self.crate_token,
-            self.stake_pool_tokens.owner,
-            "crate_token should be stake_pool_tokens.owner"
+            self.stake_pool.mint,
+            "stake_pool_tokens"
         );
         assert_keys!(self.crate_token.mint, *self.crate_mint, "crate_token.mint");
         assert_keys!(
@@ -110,6 +454,19 @@ impl<'info> Validate<'info> for MintASol<'info> {
             self.crate_token.mint,
             "mint_destination.mint"
         );
+        // only validate fee destinations if there are fees
+        if self.crate_token.issue_fee_bps != 0 {
+            assert_keys!(
+                *self.author_fee_destination,
+                self.aggregate.author_fee_destination,
+                "author_fee_destination"
+            );
+            assert_keys!(
+                *self.protocol_fee_destination,
+                self.aggregate.protocol_fee_destination,
+                "protocol_fee_destination"
+            );
+        }
 
         assert_keys!(
             self.depositor_source.mint,
@@ -121,14 +478,349 @@ impl<'info> Validate<'info> for MintASol<'info> {
             self.depositor,
             "depositor_source.owner"
         );
+        assert_keys!(
+            self.snapshot_history.aggregate,
+            self.aggregate,
+            "snapshot_history.aggregate"
+        );
+        Ok(())
+    }
+}
+
+impl<'info> Validate<'info> for MintMulti<'info> {
+    fn validate(&self) -> ProgramResult {
+        require!(!self.aggregate.is_paused(PAUSE_MINT), AggregatePaused);
+        assert_keys!(
+            self.aggregate.crate_token,
+            *self.crate_token,
+            "aggregate.crate_token"
+        );
+        assert_keys!(self.crate_token.mint, *self.crate_mint, "crate_token.mint");
+        assert_keys!(
+            self.mint_destination.mint,
+            self.crate_token.mint,
+            "mint_destination.mint"
+        );
+        // only validate fee destinations if there are fees
+        if self.crate_token.issue_fee_bps != 0 {
+            assert_keys!(
+                *self.author_fee_destination,
+                self.aggregate.author_fee_destination,
+                "author_fee_destination"
+            );
+            assert_keys!(
+                *self.protocol_fee_destination,
+                self.aggregate.protocol_fee_destination,
+                "protocol_fee_destination"
+            );
+        }
+        assert_keys!(
+            self.snapshot_history.aggregate,
+            self.aggregate,
+            "snapshot_history.aggregate"
+        );
+        Ok(())
+    }
+}
+
+impl<'info> MintMultiPool<'info> {
+    /// Validates the accounts of a deposit into the [StakePool] of the given mint.
+    pub fn validate_for_deposit(
+        &self,
+        mint_multi: &MintMulti<'info>,
+        mint: Pubkey,
+    ) -> ProgramResult {
+        assert_keys!(self.stake_pool.mint, mint, "stake_pool.mint");
+        assert_keys!(
+            self.stake_pool.aggregate,
+            mint_multi.aggregate,
+            "stake_pool.aggregate"
+        );
+        let pool = unwrap_or_err!(
+            mint_multi.aggregate.find_stake_pool(mint),
+            PoolNotFoundInSnapshot
+        );
+        require!(!pool.is_retired, StakePoolRetired);
+        require!(!self.stake_pool.is_paused(PAUSE_MINT), StakePoolPaused);
+        assert_keys!(
+            self.stake_pool_tokens.mint,
+            self.stake_pool.mint,
+            "stake_pool_tokens.mint",
+        );
+        assert_ata!(
+            *self.stake_pool_tokens,
+            *mint_multi.crate_token,
+            self.stake_pool.mint,
+            "stake_pool_tokens"
+        );
+        assert_keys!(
+            self.depositor_source.mint,
+            self.stake_pool_tokens.mint,
+            "depositor_source.mint"
+        );
+        assert_keys!(
+            self.depositor_source.owner,
+            mint_multi.depositor,
+            "depositor_source.owner"
+        );
         Ok(())
     }
 }
 
 impl<'info> Validate<'info> for SyncAndMint<'info> {
     fn validate(&self) -> ProgramResult {
-        self.sync.validate()?;
         self.mint_asol.validate()?;
         Ok(())
     }
 }
+
+impl<'info> Validate<'info> for DepositSol<'info> {
+    fn validate(&self) -> ProgramResult {
+        self.mint_asol.validate()?;
+        Ok(())
+    }
+}
+
+impl<'info> Validate<'info> for DepositSolMarinade<'info> {
+    fn validate(&self) -> ProgramResult {
+        assert_keys!(
+            self.msol_mint,
+            stake_pool_mints::marinade_msol::ID,
+            "msol_mint"
+        );
+        Ok(())
+    }
+}
+
+impl<'info> Validate<'info> for DepositSolLido<'info> {
+    fn validate(&self) -> ProgramResult {
+        assert_keys!(self.lido_program, lido_anchor::ID, "lido_program");
+        assert_keys!(
+            self.st_sol_mint,
+            stake_pool_mints::lido_stsol::ID,
+            "st_sol_mint"
+        );
+        Ok(())
+    }
+}
+
+impl<'info> Validate<'info> for DepositStakeAccount<'info> {
+    fn validate(&self) -> ProgramResult {
+        self.mint_asol.validate()?;
+        assert_keys!(
+            self.stake_history,
+            anchor_lang::solana_program::sysvar::stake_history::ID,
+            "stake_history"
+        );
+        assert_keys!(
+            self.stake_program,
+            anchor_lang::solana_program::stake::program::ID,
+            "stake_program"
+        );
+        Ok(())
+    }
+}
+
+impl<'info> Validate<'info> for DepositStakeMarinade<'info> {
+    fn validate(&self) -> ProgramResult {
+        assert_keys!(
+            self.msol_mint,
+            stake_pool_mints::marinade_msol::ID,
+            "msol_mint"
+        );
+        Ok(())
+    }
+}
+
+impl<'info> DepositStakeSplStakePool<'info> {
+    /// Validates the accounts against the SPL stake pool being deposited into.
+    pub fn validate_for_pool(&self, sync: &SyncSplStakePool<'info>) -> ProgramResult {
+        assert_keys!(
+            self.spl_stake_pool_program,
+            *sync.spl_stake_pool.owner,
+            "spl_stake_pool_program"
+        );
+        let pool = &sync.spl_stake_pool_state()?;
+        assert_keys!(self.validator_list, pool.validator_list, "validator_list");
+        // the stake account is authorized to the deposit authority before the deposit
+        assert_keys!(
+            self.deposit_authority,
+            pool.stake_deposit_authority,
+            "deposit_authority"
+        );
+        assert_keys!(self.reserve_stake, pool.reserve_stake, "reserve_stake");
+        assert_keys!(
+            self.manager_fee_account,
+            pool.manager_fee_account,
+            "manager_fee_account"
+        );
+        assert_keys!(self.pool_mint, pool.pool_mint, "pool_mint");
+        Ok(())
+    }
+}
+
+impl<'info> MintReferrer<'info> {
+    /// Validates the referrer of a deposit by the `depositor`.
+    pub fn validate_for_mint(
+        &self,
+        aggregate: &Account<'info, Aggregate>,
+        depositor: Pubkey,
+    ) -> ProgramResult {
+        assert_keys!(self.referrer.aggregate, *aggregate, "referrer.aggregate");
+        assert_keys!(
+            *self.referrer_fee_destination,
+            self.referrer.fee_destination,
+            "referrer_fee_destination"
+        );
+        // only rejects the trivial case: the depositor may still use a referrer owned by another wallet
+        require!(self.referrer.owner != depositor, SelfReferral);
+        Ok(())
+    }
+}
+
+impl<'info> Validate<'info> for RedeemASol<'info> {
+    fn validate(&self) -> ProgramResult {
+        assert_keys!(
+            self.aggregate.crate_token,
+            *self.crate_token,
+            "aggregate.crate_token"
+        );
+        assert_keys!(self.crate_token.mint, *self.crate_mint, "crate_token.mint");
+        assert_keys!(
+            self.redeemer_source.mint,
+            self.crate_token.mint,
+            "redeemer_source.mint"
+        );
+        assert_keys!(
+            self.redeemer_source.owner,
+            self.redeemer,
+            "redeemer_source.owner"
+        );
+        // crates of the initial release are withdrawn from by `crate_redeem_in_kind`
+        require!(
+            self.crate_token.withdraw_authority == self.aggregate.key(),
+            WithdrawAuthorityNotAggregate
+        );
+        require!(!self.aggregate.is_paused(PAUSE_REDEEM), AggregatePaused);
+        assert_keys!(
+            self.snapshot_history.aggregate,
+            self.aggregate,
+            "snapshot_history.aggregate"
+        );
+        Ok(())
+    }
+}
+
+impl<'info> Validate<'info> for Refresh<'info> {
+    fn validate(&self) -> ProgramResult {
+        assert_keys!(
+            self.aggregate.crate_token,
+            *self.crate_token,
+            "aggregate.crate_token"
+        );
+        assert_keys!(self.crate_token.mint, *self.crate_mint, "crate_token.mint");
+        assert_keys!(
+            self.snapshot_history.aggregate,
+            self.aggregate,
+            "snapshot_history.aggregate"
+        );
+        Ok(())
+    }
+}
+
+impl<'info> Validate<'info> for NewExchangeRateOracle<'info> {
+    fn validate(&self) -> ProgramResult {
+        Ok(())
+    }
+}
+
+impl<'info> Validate<'info> for GetExchangeRate<'info> {
+    fn validate(&self) -> ProgramResult {
+        assert_keys!(
+            self.aggregate.crate_token,
+            *self.crate_token,
+            "aggregate.crate_token"
+        );
+        assert_keys!(self.crate_token.mint, *self.crate_mint, "crate_token.mint");
+        assert_keys!(
+            self.exchange_rate_oracle.aggregate,
+            self.aggregate,
+            "exchange_rate_oracle.aggregate"
+        );
+        Ok(())
+    }
+}
+
+impl<'info> Validate<'info> for SyncAndRedeem<'info> {
+    fn validate(&self) -> ProgramResult {
+        self.redeem_asol.validate()?;
+        Ok(())
+    }
+}
+
+impl<'info> Validate<'info> for SyncAndWithdrawSingle<'info> {
+    fn validate(&self) -> ProgramResult {
+        self.redeem_asol.validate()?;
+        let pool = unwrap_or_err!(
+            self.redeem_asol
+                .aggregate
+                .stake_pools
+                .iter()
+                .find(|pool| pool.mint == self.pool.stake_pool.mint),
+            PoolNotFoundInSnapshot
+        );
+        self.pool.validate_for_pool(
+            &self.redeem_asol.aggregate,
+            &self.redeem_asol.crate_token,
+            pool,
+        )?;
+        require!(
+            !self.pool.stake_pool.is_paused(PAUSE_REDEEM),
+            StakePoolPaused
+        );
+        Ok(())
+    }
+}
+
+impl<'info> RedeemASolPool<'info> {
+    /// Validates the accounts of a stake pool being redeemed from.
+    pub fn validate_for_pool(
+        &self,
+        aggregate: &Account<'info, Aggregate>,
+        crate_token: &Account<'info, crate_token::CrateToken>,
+        pool: &StakePoolMeta,
+    ) -> ProgramResult {
+        assert_keys!(
+            self.stake_pool.aggregate,
+            *aggregate,
+            "stake_pool.aggregate"
+        );
+        assert_keys!(self.stake_pool.mint, pool.mint, "stake_pool.mint");
+        assert_keys!(
+            self.crate_underlying.mint,
+            pool.mint,
+            "crate_underlying.mint"
+        );
+        assert_keys!(
+            self.crate_underlying.owner,
+            *crate_token,
+            "crate_token should be crate_underlying.owner"
+        );
+        assert_keys!(
+            self.withdraw_destination.mint,
+            pool.mint,
+            "withdraw_destination.mint"
+        );
+        Ok(())
+    }
+
+    /// Validates that the stake pool tokens are withdrawn from the synchronized reserves.
+    pub fn validate_reserves(&self, sync: &SyncStakePool<'info>) -> ProgramResult {
+        assert_keys!(
+            *self.crate_underlying,
+            *sync.crate_reserves(),
+            "crate_underlying"
+        );
+        Ok(())
+    }
+}
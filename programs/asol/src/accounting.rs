@@ -1,20 +1,27 @@
 use anchor_lang::prelude::*;
 use anchor_spl::token::TokenAccount;
-use num_traits::cast::ToPrimitive;
 use vipers::unwrap_int;
 
-use crate::{state::AccountingMethod, SyncLido, SyncMarinade, SOL};
+use crate::{state::AccountingMethod, Rounding, SyncLido, SyncMarinade, SyncSplStakePool, SOL};
 
 /// Can account for the amount of SOL in a stake pool.
 pub trait Accountant<'info> {
     /// The accounting method to use for the stake pool.
     const METHOD: AccountingMethod;
 
-    /// Calculates the value of the stake pool token amount in SOL.
+    /// Calculates the value of the stake pool token amount in SOL, rounded down.
     fn sol_value(&self, amount: u64) -> Result<SOL, ProgramError>;
 
     /// Gets the [TokenAccount] of stake pool tokens associated with the Crate.
     fn crate_reserves(&self) -> &TokenAccount;
+
+    /// Gets the address of the account holding the state of the stake pool.
+    fn pool_state(&self) -> Pubkey;
+
+    /// Checks that the exchange rate used by [Accountant::sol_value] is up to date and can be relied upon.
+    fn check_exchange_rate(&self, _clock: &Clock) -> ProgramResult {
+        Ok(())
+    }
 }
 
 impl<'info> Accountant<'info> for SyncMarinade<'info> {
@@ -22,16 +29,30 @@ impl<'info> Accountant<'info> for SyncMarinade<'info> {
 
     fn sol_value(&self, amount: u64) -> Result<SOL, ProgramError> {
         let msol_price: u64 = self.marinade.msol_price;
-        let sol_value = unwrap_int!((amount as u128)
-            .checked_mul(msol_price.into())
-            .and_then(|v| v.checked_div(0x1_0000_0000_u128))
-            .and_then(|v| v.to_u64()));
-        Ok(SOL::from(sol_value))
+        Ok(unwrap_int!(SOL::from(amount).checked_mul_div(
+            msol_price,
+            0x1_0000_0000,
+            Rounding::Down
+        )))
     }
 
     fn crate_reserves(&self) -> &TokenAccount {
         &self.marinade_stake_pool_tokens
     }
+
+    fn pool_state(&self) -> Pubkey {
+        self.marinade.key()
+    }
+
+    /// Marinade is in emergency mode while stake is being emergency unstaked,
+    /// during which the mSOL price cannot be relied upon.
+    fn check_exchange_rate(&self, _clock: &Clock) -> ProgramResult {
+        require!(
+            self.marinade.emergency_cooling_down == 0,
+            MarinadeEmergencyMode
+        );
+        Ok(())
+    }
 }
 
 impl<'info> Accountant<'info> for SyncLido<'info> {
@@ -39,14 +60,87 @@ impl<'info> Accountant<'info> for SyncLido<'info> {
 
     fn sol_value(&self, amount: u64) -> Result<SOL, ProgramError> {
         let lido = &*self.lido;
-        let sol_value = unwrap_int!((amount as u128)
-            .checked_mul(lido.exchange_rate.sol_balance.0.into())
-            .and_then(|v| v.checked_div(lido.exchange_rate.st_sol_supply.0.into()))
-            .and_then(|v| v.to_u64()));
-        Ok(SOL::from(sol_value))
+        Ok(unwrap_int!(SOL::from(amount).checked_mul_div(
+            lido.exchange_rate.sol_balance.0,
+            lido.exchange_rate.st_sol_supply.0,
+            Rounding::Down
+        )))
     }
 
     fn crate_reserves(&self) -> &TokenAccount {
         &self.lido_stake_pool_tokens
     }
+
+    fn pool_state(&self) -> Pubkey {
+        self.lido.key()
+    }
+
+    /// The Lido exchange rate is only updated once per epoch, so it is stale
+    /// until `UpdateExchangeRate` has been called in the current epoch.
+    fn check_exchange_rate(&self, clock: &Clock) -> ProgramResult {
+        check_updated_in_epoch(self.lido.exchange_rate.computed_in_epoch, clock)
+    }
+}
+
+impl<'info> Accountant<'info> for SyncSplStakePool<'info> {
+    const METHOD: AccountingMethod = AccountingMethod::SplStakePool;
+
+    fn sol_value(&self, amount: u64) -> Result<SOL, ProgramError> {
+        let spl_stake_pool = self.spl_stake_pool_state()?;
+        Ok(unwrap_int!(SOL::from(amount).checked_mul_div(
+            spl_stake_pool.total_lamports,
+            spl_stake_pool.pool_token_supply,
+            Rounding::Down
+        )))
+    }
+
+    fn crate_reserves(&self) -> &TokenAccount {
+        &self.spl_stake_pool_tokens
+    }
+
+    fn pool_state(&self) -> Pubkey {
+        self.spl_stake_pool.key()
+    }
+
+    /// The SPL stake pool balances are only updated once per epoch, so they are stale
+    /// until `UpdateStakePoolBalance` has been called in the current epoch.
+    fn check_exchange_rate(&self, clock: &Clock) -> ProgramResult {
+        check_updated_in_epoch(self.spl_stake_pool_state()?.last_update_epoch, clock)
+    }
+}
+
+impl<'info> SyncSplStakePool<'info> {
+    /// Deserializes the [SyncSplStakePool::spl_stake_pool].
+    /// Its owner is checked against the [crate::StakePoolMeta::pool_program] when the accounts are loaded.
+    pub fn spl_stake_pool_state(&self) -> Result<spl_stake_pool_anchor::StakePool, ProgramError> {
+        spl_stake_pool_anchor::StakePool::try_deserialize(
+            &mut &self.spl_stake_pool.try_borrow_data()?[..],
+        )
+    }
+}
+
+/// Checks that an exchange rate last updated in `updated_epoch` is from the current epoch.
+fn check_updated_in_epoch(updated_epoch: u64, clock: &Clock) -> ProgramResult {
+    require!(updated_epoch >= clock.epoch, StaleExchangeRate);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_updated_in_epoch() {
+        let clock = Clock {
+            epoch: 250,
+            ..Clock::default()
+        };
+        assert!(check_updated_in_epoch(250, &clock).is_ok());
+        assert!(check_updated_in_epoch(251, &clock).is_ok());
+        assert_eq!(
+            check_updated_in_epoch(249, &clock).unwrap_err(),
+            crate::ErrorCode::StaleExchangeRate.into()
+        );
+        assert!(check_updated_in_epoch(0, &clock).is_err());
+    }
 }
@@ -0,0 +1,288 @@
+use crate::{
+    DepositSol, DepositSolLido, DepositSolMarinade, DepositStakeAccount, DepositStakeMarinade,
+    DepositStakeSplStakePool, MintASol, MintOptionalAccounts, MintOptions, Snapshot, SyncAll,
+    SyncStakePool,
+};
+use anchor_lang::{
+    prelude::*,
+    solana_program::{
+        program::invoke,
+        stake::{instruction::authorize, state::StakeAuthorize},
+    },
+};
+use vipers::{unwrap_int, unwrap_or_err, validate::Validate};
+
+impl<'info> DepositSol<'info> {
+    /// Deposits SOL into the stake pool furthest below its max weight, then mints aSOL for the received stake pool tokens.
+    pub fn deposit_sol(
+        &mut self,
+        remaining_accounts: &[AccountInfo<'info>],
+        lamports: u64,
+        min_mint_amount: u64,
+        options: MintOptions,
+    ) -> ProgramResult {
+        require!(lamports > 0, ZeroDeposit);
+
+        // sync accounts come first, followed by the deposit accounts
+        let (sync, deposit_accounts) =
+            SyncAll::load(&self.mint_asol.aggregate, remaining_accounts)?;
+        sync.check_exchange_rates()?;
+        let snapshot = sync.sync(&mut self.mint_asol.aggregate, &self.mint_asol.crate_mint)?;
+
+        let aggregate = &self.mint_asol.aggregate;
+        let index = unwrap_or_err!(
+            snapshot.find_pool_with_most_headroom(|pool| {
+                aggregate
+                    .find_stake_pool(pool.pool_mint)
+                    .filter(|meta| meta.accepts_sol_deposits())
+                    .map(|meta| meta.max_weight_bps)
+            })?,
+            NoSolDepositPool
+        );
+        require!(
+            aggregate.stake_pools[index].mint == self.mint_asol.stake_pool.mint,
+            NotLeastWeightedPool
+        );
+        let minter = &sync.stake_pools[index];
+
+        let amount_before = self.mint_asol.depositor_source.amount;
+        let accounts = &mut &deposit_accounts[..];
+        match minter {
+            SyncStakePool::Marinade(sync) => {
+                let deposit: DepositSolMarinade =
+                    Accounts::try_accounts(&crate::ID, accounts, &[])?;
+                deposit.validate()?;
+                self.deposit_marinade(&deposit, sync.marinade.to_account_info(), lamports)?;
+            }
+            SyncStakePool::Lido(sync) => {
+                let deposit: DepositSolLido = Accounts::try_accounts(&crate::ID, accounts, &[])?;
+                deposit.validate()?;
+                self.deposit_lido(&deposit, sync.lido.to_account_info(), lamports)?;
+            }
+            SyncStakePool::SplStakePool(_) => {
+                return Err(crate::ErrorCode::AccountingMethodMismatch.into());
+            }
+        }
+        let optional = MintOptionalAccounts::load(
+            &self.mint_asol.aggregate,
+            self.mint_asol.depositor.key(),
+            options,
+            accounts,
+        )?;
+
+        self.mint_asol.mint_asol_for_received(
+            &snapshot,
+            minter,
+            optional,
+            amount_before,
+            min_mint_amount,
+        )
+    }
+
+    /// Deposits SOL into Marinade in exchange for mSOL.
+    fn deposit_marinade(
+        &self,
+        deposit: &DepositSolMarinade<'info>,
+        state: AccountInfo<'info>,
+        lamports: u64,
+    ) -> ProgramResult {
+        marinade::cpi::deposit(
+            CpiContext::new(
+                deposit.marinade_program.to_account_info(),
+                marinade::cpi::accounts::Deposit {
+                    state,
+                    msol_mint: deposit.msol_mint.to_account_info(),
+                    liq_pool_sol_leg_pda: deposit.liq_pool_sol_leg_pda.to_account_info(),
+                    liq_pool_msol_leg: deposit.liq_pool_msol_leg.to_account_info(),
+                    liq_pool_msol_leg_authority: deposit
+                        .liq_pool_msol_leg_authority
+                        .to_account_info(),
+                    reserve_pda: deposit.reserve_pda.to_account_info(),
+                    transfer_from: self.mint_asol.depositor.to_account_info(),
+                    mint_to: self.mint_asol.depositor_source.to_account_info(),
+                    msol_mint_authority: deposit.msol_mint_authority.to_account_info(),
+                    system_program: self.system_program.to_account_info(),
+                    token_program: self.mint_asol.token_program.to_account_info(),
+                },
+            ),
+            lamports,
+        )
+    }
+
+    /// Deposits SOL into Lido in exchange for stSOL.
+    fn deposit_lido(
+        &self,
+        deposit: &DepositSolLido<'info>,
+        lido: AccountInfo<'info>,
+        lamports: u64,
+    ) -> ProgramResult {
+        lido_anchor::cpi::deposit(
+            CpiContext::new(
+                deposit.lido_program.to_account_info(),
+                lido_anchor::cpi::accounts::Deposit {
+                    lido,
+                    user: self.mint_asol.depositor.to_account_info(),
+                    recipient: self.mint_asol.depositor_source.to_account_info(),
+                    st_sol_mint: deposit.st_sol_mint.to_account_info(),
+                    reserve_account: deposit.reserve_account.to_account_info(),
+                    mint_authority: deposit.mint_authority.to_account_info(),
+                    spl_token: self.mint_asol.token_program.to_account_info(),
+                    system_program: self.system_program.to_account_info(),
+                },
+            ),
+            lamports,
+        )
+    }
+}
+
+impl<'info> DepositStakeAccount<'info> {
+    /// Deposits a stake account into the [MintASol::stake_pool], then mints aSOL for the received stake pool tokens.
+    pub fn deposit_stake_account(
+        &mut self,
+        remaining_accounts: &[AccountInfo<'info>],
+        validator_index: u32,
+        min_mint_amount: u64,
+        options: MintOptions,
+    ) -> ProgramResult {
+        // sync accounts come first, followed by the deposit accounts
+        let (sync, deposit_accounts) =
+            SyncAll::load(&self.mint_asol.aggregate, remaining_accounts)?;
+        sync.check_exchange_rates()?;
+        let snapshot = sync.sync(&mut self.mint_asol.aggregate, &self.mint_asol.crate_mint)?;
+        let minter = sync.find_stake_pool(self.mint_asol.stake_pool.mint)?;
+
+        let amount_before = self.mint_asol.depositor_source.amount;
+        let accounts = &mut &deposit_accounts[..];
+        match minter {
+            SyncStakePool::Marinade(sync) => {
+                let deposit: DepositStakeMarinade =
+                    Accounts::try_accounts(&crate::ID, accounts, &[])?;
+                deposit.validate()?;
+                self.deposit_stake_marinade(
+                    &deposit,
+                    sync.marinade.to_account_info(),
+                    validator_index,
+                )?;
+            }
+            SyncStakePool::SplStakePool(sync) => {
+                let deposit: DepositStakeSplStakePool =
+                    Accounts::try_accounts(&crate::ID, accounts, &[])?;
+                deposit.validate_for_pool(sync)?;
+                self.deposit_stake_spl_stake_pool(&deposit, sync.spl_stake_pool.to_account_info())?;
+            }
+            SyncStakePool::Lido(_) => {
+                return Err(crate::ErrorCode::AccountingMethodMismatch.into());
+            }
+        }
+        let optional = MintOptionalAccounts::load(
+            &self.mint_asol.aggregate,
+            self.mint_asol.depositor.key(),
+            options,
+            accounts,
+        )?;
+
+        self.mint_asol.mint_asol_for_received(
+            &snapshot,
+            minter,
+            optional,
+            amount_before,
+            min_mint_amount,
+        )
+    }
+
+    /// Deposits the stake account into Marinade in exchange for mSOL.
+    fn deposit_stake_marinade(
+        &self,
+        deposit: &DepositStakeMarinade<'info>,
+        state: AccountInfo<'info>,
+        validator_index: u32,
+    ) -> ProgramResult {
+        marinade::cpi::deposit_stake_account(
+            CpiContext::new(
+                deposit.marinade_program.to_account_info(),
+                marinade::cpi::accounts::DepositStakeAccount {
+                    state,
+                    validator_list: deposit.validator_list.to_account_info(),
+                    stake_list: deposit.stake_list.to_account_info(),
+                    stake_account: self.stake_account.to_account_info(),
+                    stake_authority: self.mint_asol.depositor.to_account_info(),
+                    duplication_flag: deposit.duplication_flag.to_account_info(),
+                    rent_payer: self.mint_asol.depositor.to_account_info(),
+                    msol_mint: deposit.msol_mint.to_account_info(),
+                    mint_to: self.mint_asol.depositor_source.to_account_info(),
+                    msol_mint_authority: deposit.msol_mint_authority.to_account_info(),
+                    clock: self.clock.to_account_info(),
+                    rent: self.rent.to_account_info(),
+                    system_program: self.system_program.to_account_info(),
+                    token_program: self.mint_asol.token_program.to_account_info(),
+                    stake_program: self.stake_program.to_account_info(),
+                },
+            ),
+            validator_index,
+        )
+    }
+
+    /// Deposits the stake account into an SPL stake pool in exchange for pool tokens.
+    fn deposit_stake_spl_stake_pool(
+        &self,
+        deposit: &DepositStakeSplStakePool<'info>,
+        stake_pool: AccountInfo<'info>,
+    ) -> ProgramResult {
+        // the stake pool requires the deposit authority to control the stake account
+        for stake_authorize in &[StakeAuthorize::Staker, StakeAuthorize::Withdrawer] {
+            invoke(
+                &authorize(
+                    self.stake_account.key,
+                    self.mint_asol.depositor.key,
+                    deposit.deposit_authority.key,
+                    *stake_authorize,
+                    None,
+                ),
+                &[
+                    self.stake_account.to_account_info(),
+                    self.clock.to_account_info(),
+                    self.mint_asol.depositor.to_account_info(),
+                    self.stake_program.to_account_info(),
+                ],
+            )?;
+        }
+
+        spl_stake_pool_anchor::cpi::deposit_stake(CpiContext::new(
+            deposit.spl_stake_pool_program.to_account_info(),
+            spl_stake_pool_anchor::cpi::accounts::DepositStake {
+                stake_pool,
+                validator_list: deposit.validator_list.to_account_info(),
+                deposit_authority: deposit.deposit_authority.to_account_info(),
+                withdraw_authority: deposit.withdraw_authority.to_account_info(),
+                stake: self.stake_account.to_account_info(),
+                validator_stake: deposit.validator_stake.to_account_info(),
+                reserve_stake: deposit.reserve_stake.to_account_info(),
+                pool_tokens_to: self.mint_asol.depositor_source.to_account_info(),
+                manager_fee_account: deposit.manager_fee_account.to_account_info(),
+                // referral fees are returned to the depositor
+                referrer_pool_tokens_account: self.mint_asol.depositor_source.to_account_info(),
+                pool_mint: deposit.pool_mint.to_account_info(),
+                clock: self.clock.to_account_info(),
+                stake_history: self.stake_history.to_account_info(),
+                token_program: self.mint_asol.token_program.to_account_info(),
+                stake_program: self.stake_program.to_account_info(),
+            },
+        ))
+    }
+}
+
+impl<'info> MintASol<'info> {
+    /// Mints aSOL for the stake pool tokens the depositor received since its balance was `amount_before`.
+    fn mint_asol_for_received(
+        &mut self,
+        snapshot: &Snapshot,
+        minter: &SyncStakePool<'info>,
+        optional: MintOptionalAccounts<'info>,
+        amount_before: u64,
+        min_mint_amount: u64,
+    ) -> ProgramResult {
+        self.depositor_source.reload()?;
+        let deposit_amount = unwrap_int!(self.depositor_source.amount.checked_sub(amount_before));
+        self.mint_asol(snapshot, minter, optional, deposit_amount, min_mint_amount)
+    }
+}
@@ -16,6 +16,62 @@ pub struct NewAggregateEvent {
     pub timestamp: i64,
 }
 
+/// Emitted when a [SnapshotHistory] is created.
+#[event]
+pub struct NewSnapshotHistoryEvent {
+    /// Aggregate
+    #[index]
+    pub aggregate: Pubkey,
+    /// Snapshot history
+    pub snapshot_history: Pubkey,
+    /// Timestamp of the event.
+    pub timestamp: i64,
+}
+
+/// Emitted when a [Position] is created.
+#[event]
+pub struct NewPositionEvent {
+    /// Aggregate
+    #[index]
+    pub aggregate: Pubkey,
+    /// Owner
+    #[index]
+    pub owner: Pubkey,
+    /// Position
+    pub position: Pubkey,
+    /// Timestamp of the event.
+    pub timestamp: i64,
+}
+
+/// Emitted when a [Referrer] is created.
+#[event]
+pub struct NewReferrerEvent {
+    /// Aggregate
+    #[index]
+    pub aggregate: Pubkey,
+    /// Owner
+    #[index]
+    pub owner: Pubkey,
+    /// Referrer
+    pub referrer: Pubkey,
+    /// The [Referrer::fee_destination].
+    pub fee_destination: Pubkey,
+    /// Timestamp of the event.
+    pub timestamp: i64,
+}
+
+/// Emitted when an [ExchangeRateOracle] is created.
+#[event]
+pub struct NewExchangeRateOracleEvent {
+    /// Aggregate
+    #[index]
+    pub aggregate: Pubkey,
+    /// Exchange rate oracle
+    pub exchange_rate_oracle: Pubkey,
+    /// Timestamp of the event.
+    pub timestamp: i64,
+}
+
 /// Emitted when a [StakePool] is added.
 #[event]
 pub struct AddStakePoolEvent {
@@ -32,6 +88,90 @@ pub struct AddStakePoolEvent {
     pub mint: Pubkey,
     /// The accounting method used.
     pub accounting_method: AccountingMethod,
+    /// The [StakePoolMeta::pool_state].
+    pub pool_state: Pubkey,
+
+    /// Timestamp of the event.
+    pub timestamp: i64,
+}
+
+/// Emitted when a [StakePool] is retired.
+#[event]
+pub struct RetireStakePoolEvent {
+    /// Aggregate
+    #[index]
+    pub aggregate: Pubkey,
+    /// Stake pool
+    #[index]
+    pub stake_pool: Pubkey,
+
+    /// The [Aggregate::curator].
+    pub curator: Pubkey,
+    /// The [Mint].
+    pub mint: Pubkey,
+
+    /// Timestamp of the event.
+    pub timestamp: i64,
+}
+
+/// Emitted when a [StakePool] is removed.
+#[event]
+pub struct RemoveStakePoolEvent {
+    /// Aggregate
+    #[index]
+    pub aggregate: Pubkey,
+    /// Stake pool
+    #[index]
+    pub stake_pool: Pubkey,
+
+    /// The [Aggregate::curator].
+    pub curator: Pubkey,
+    /// The [Mint].
+    pub mint: Pubkey,
+    /// Untracked stake pool tokens withdrawn from the reserves.
+    pub dust: u64,
+
+    /// Timestamp of the event.
+    pub timestamp: i64,
+}
+
+/// Emitted when the deposit limits of a [StakePool] are modified.
+#[event]
+pub struct SetStakePoolLimitsEvent {
+    /// Aggregate
+    #[index]
+    pub aggregate: Pubkey,
+    /// Stake pool
+    #[index]
+    pub stake_pool: Pubkey,
+
+    /// The [Aggregate::curator].
+    pub curator: Pubkey,
+    /// The new [StakePool::max_weight_bps].
+    pub max_weight_bps: u16,
+    /// The new [StakePool::deposit_cap].
+    pub deposit_cap: u64,
+
+    /// Timestamp of the event.
+    pub timestamp: i64,
+}
+
+/// Emitted when the price band of a [StakePool] is modified.
+#[event]
+pub struct SetStakePoolPriceBandEvent {
+    /// Aggregate
+    #[index]
+    pub aggregate: Pubkey,
+    /// Stake pool
+    #[index]
+    pub stake_pool: Pubkey,
+
+    /// The [Aggregate::curator].
+    pub curator: Pubkey,
+    /// The new [StakePool::min_sol_for_1e9].
+    pub min_sol_for_1e9: u64,
+    /// The new [StakePool::max_sol_for_1e9].
+    pub max_sol_for_1e9: u64,
 
     /// Timestamp of the event.
     pub timestamp: i64,
@@ -55,6 +195,116 @@ pub struct SetCuratorEvent {
     pub timestamp: i64,
 }
 
+/// Emitted when a new [Aggregate::curator] is proposed.
+#[event]
+pub struct SetPendingCuratorEvent {
+    /// Aggregate
+    #[index]
+    pub aggregate: Pubkey,
+
+    /// The current [Aggregate::curator].
+    pub curator: Pubkey,
+    /// The new [Aggregate::pending_curator].
+    pub pending_curator: Pubkey,
+    /// The [Aggregate::curator_setter].
+    pub curator_setter: Pubkey,
+
+    /// Timestamp of the event.
+    pub timestamp: i64,
+}
+
+/// Emitted when a new [Aggregate::curator_setter] is proposed.
+#[event]
+pub struct SetPendingCuratorSetterEvent {
+    /// Aggregate
+    #[index]
+    pub aggregate: Pubkey,
+
+    /// The current [Aggregate::curator_setter].
+    pub curator_setter: Pubkey,
+    /// The new [Aggregate::pending_curator_setter].
+    pub pending_curator_setter: Pubkey,
+
+    /// Timestamp of the event.
+    pub timestamp: i64,
+}
+
+/// Emitted when an [Aggregate]'s curator setter is modified.
+#[event]
+pub struct SetCuratorSetterEvent {
+    /// Aggregate
+    #[index]
+    pub aggregate: Pubkey,
+
+    /// The new [Aggregate::curator_setter].
+    pub curator_setter: Pubkey,
+    /// The previous [Aggregate::curator_setter].
+    pub previous_curator_setter: Pubkey,
+
+    /// Timestamp of the event.
+    pub timestamp: i64,
+}
+
+/// Emitted when an [Aggregate]'s guardian is modified.
+#[event]
+pub struct SetGuardianEvent {
+    /// Aggregate
+    #[index]
+    pub aggregate: Pubkey,
+
+    /// The new [Aggregate::guardian].
+    pub guardian: Pubkey,
+    /// The previous [Aggregate::guardian].
+    pub previous_guardian: Pubkey,
+    /// The [Aggregate::curator_setter].
+    pub curator_setter: Pubkey,
+
+    /// Timestamp of the event.
+    pub timestamp: i64,
+}
+
+/// Emitted when operations on an [Aggregate] or [StakePool] are paused.
+#[event]
+pub struct PauseEvent {
+    /// Aggregate
+    #[index]
+    pub aggregate: Pubkey,
+    /// Stake pool, if a single stake pool was paused.
+    #[index]
+    pub stake_pool: Option<Pubkey>,
+
+    /// The [Aggregate::guardian].
+    pub guardian: Pubkey,
+    /// The flags that were paused.
+    pub flags: u8,
+    /// All paused flags after the pause.
+    pub paused: u8,
+
+    /// Timestamp of the event.
+    pub timestamp: i64,
+}
+
+/// Emitted when operations on an [Aggregate] or [StakePool] are unpaused.
+#[event]
+pub struct UnpauseEvent {
+    /// Aggregate
+    #[index]
+    pub aggregate: Pubkey,
+    /// Stake pool, if a single stake pool was unpaused.
+    #[index]
+    pub stake_pool: Option<Pubkey>,
+
+    /// The [Aggregate::guardian].
+    pub guardian: Pubkey,
+    /// The flags that were unpaused.
+    pub flags: u8,
+    /// All paused flags after the unpause.
+    pub paused: u8,
+
+    /// Timestamp of the event.
+    pub timestamp: i64,
+}
+
 /// Emitted when ASol is minted.
 #[event]
 pub struct MintASolEvent {
@@ -76,6 +326,264 @@ pub struct MintASolEvent {
     /// Amount of aSOL minted.
     pub mint_amount: u64,
 
+    /// The [Referrer::owner] of the deposit, if any.
+    pub referrer: Option<Pubkey>,
+    /// Amount of aSOL paid to the referrer out of the issue fee.
+    /// For [crate::asol::mint_multi], this is reported on the [MintMultiEvent] instead.
+    pub referral_fee: u64,
+
+    /// Timestamp of the event.
+    pub timestamp: i64,
+}
+
+/// Emitted once per [crate::asol::mint_multi], after a [MintASolEvent] per deposit.
+#[event]
+pub struct MintMultiEvent {
+    /// Depositor
+    #[index]
+    pub depositor: Pubkey,
+
+    /// Mints of the stake pool tokens deposited.
+    pub stake_pool_mints: Vec<Pubkey>,
+    /// Total [SOL] value of the deposits.
+    pub deposit_sol_value: u64,
+    /// Total amount of aSOL minted.
+    pub mint_amount: u64,
+
+    /// The [Referrer::owner] of the deposits, if any.
+    pub referrer: Option<Pubkey>,
+    /// Amount of aSOL paid to the referrer out of the issue fee.
+    pub referral_fee: u64,
+
+    /// Timestamp of the event.
+    pub timestamp: i64,
+}
+
+/// Emitted when aSOL is redeemed.
+#[event]
+pub struct RedeemASolEvent {
+    /// Redeemer
+    #[index]
+    pub redeemer: Pubkey,
+
+    /// Amount of aSOL burned.
+    pub redeem_amount: u64,
+
+    /// Amount of each stake pool token withdrawn before fees,
+    /// in the same order as [Aggregate::stake_pools].
+    pub withdraw_amounts: Vec<u64>,
+
+    /// Timestamp of the event.
+    pub timestamp: i64,
+}
+
+/// Emitted when aSOL is withdrawn for a single stake pool token.
+#[event]
+pub struct WithdrawSingleEvent {
+    /// Redeemer
+    #[index]
+    pub redeemer: Pubkey,
+
+    /// The mint of the stake pool token withdrawn.
+    #[index]
+    pub stake_pool_mint: Pubkey,
+
+    /// Amount of aSOL burned.
+    pub redeem_amount: u64,
+
+    /// Amount of stake pool tokens withdrawn, before the [crate_token::CrateToken] withdraw fee.
+    pub withdraw_amount: u64,
+
+    /// Amount of stake pool tokens kept in the reserves as the single withdraw fee.
+    pub fee_amount: u64,
+
+    /// Timestamp of the event.
+    pub timestamp: i64,
+}
+
+/// Emitted when an [Aggregate]'s single withdraw fee is modified.
+#[event]
+pub struct SetSingleWithdrawFeeEvent {
+    /// Aggregate
+    #[index]
+    pub aggregate: Pubkey,
+
+    /// The [Aggregate::curator].
+    pub curator: Pubkey,
+    /// The new [Aggregate::single_withdraw_fee_bps].
+    pub single_withdraw_fee_bps: u16,
+    /// The previous [Aggregate::single_withdraw_fee_bps].
+    pub previous_single_withdraw_fee_bps: u16,
+
+    /// Timestamp of the event.
+    pub timestamp: i64,
+}
+
+/// Emitted when an [Aggregate]'s [crate_token::CrateToken] fees are modified.
+#[event]
+pub struct SetFeesEvent {
+    /// Aggregate
+    #[index]
+    pub aggregate: Pubkey,
+
+    /// The [Aggregate::curator].
+    pub curator: Pubkey,
+    /// The new issue fee.
+    pub issue_fee_bps: u16,
+    /// The previous issue fee.
+    pub previous_issue_fee_bps: u16,
+    /// The new withdraw fee.
+    pub withdraw_fee_bps: u16,
+    /// The previous withdraw fee.
+    pub previous_withdraw_fee_bps: u16,
+
+    /// Timestamp of the event.
+    pub timestamp: i64,
+}
+
+/// Emitted when an [Aggregate]'s issue fee destinations are modified.
+#[event]
+pub struct SetFeeDestinationsEvent {
+    /// Aggregate
+    #[index]
+    pub aggregate: Pubkey,
+
+    /// The [Aggregate::curator].
+    pub curator: Pubkey,
+    /// The new [Aggregate::author_fee_destination].
+    pub author_fee_destination: Pubkey,
+    /// The new [Aggregate::protocol_fee_destination].
+    pub protocol_fee_destination: Pubkey,
+
+    /// Timestamp of the event.
+    pub timestamp: i64,
+}
+
+/// Emitted when fees owned by an [Aggregate] are swept to a recipient.
+#[event]
+pub struct SweepFeesEvent {
+    /// Aggregate
+    #[index]
+    pub aggregate: Pubkey,
+
+    /// The [Aggregate::curator].
+    pub curator: Pubkey,
+    /// Mint of the swept fees.
+    pub mint: Pubkey,
+    /// Fee account the fees were swept from.
+    pub fee_source: Pubkey,
+    /// Account receiving the fees.
+    pub fee_recipient: Pubkey,
+    /// Amount of tokens swept.
+    pub amount: u64,
+
+    /// Timestamp of the event.
+    pub timestamp: i64,
+}
+
+/// Emitted when an [Aggregate]'s performance fee or treasury is modified.
+#[event]
+pub struct SetPerformanceFeeEvent {
+    /// Aggregate
+    #[index]
+    pub aggregate: Pubkey,
+
+    /// The [Aggregate::curator].
+    pub curator: Pubkey,
+    /// The new [Aggregate::performance_fee_bps].
+    pub performance_fee_bps: u16,
+    /// The previous [Aggregate::performance_fee_bps].
+    pub previous_performance_fee_bps: u16,
+    /// The new [Aggregate::treasury].
+    pub treasury: Pubkey,
+
+    /// Timestamp of the event.
+    pub timestamp: i64,
+}
+
+/// Emitted when the [Aggregate::referral_fee_bps] is set.
+#[event]
+pub struct SetReferralFeeEvent {
+    /// Aggregate
+    #[index]
+    pub aggregate: Pubkey,
+
+    /// The [Aggregate::curator].
+    pub curator: Pubkey,
+    /// The new [Aggregate::referral_fee_bps].
+    pub referral_fee_bps: u16,
+    /// The previous [Aggregate::referral_fee_bps].
+    pub previous_referral_fee_bps: u16,
+
+    /// Timestamp of the event.
+    pub timestamp: i64,
+}
+
+/// Emitted when a performance fee is accrued on a sync.
+/// Leftover reserves swept to the treasury are also reported here, with no `accrued_sol`.
+#[event]
+pub struct PerformanceFeeEvent {
+    /// Aggregate
+    #[index]
+    pub aggregate: Pubkey,
+
+    /// SOL yield earned by aSOL holders since the previous high-water mark.
+    pub accrued_sol: u64,
+    /// SOL value of the fee.
+    pub fee_sol: u64,
+    /// aSOL accrued to the [Aggregate::treasury].
+    pub fee_asol: u64,
+    /// The new [Aggregate::high_water_mark].
+    pub high_water_mark: u64,
+
+    /// Timestamp of the event.
+    pub timestamp: i64,
+}
+
+/// Emitted when the accrued performance fee is minted to the treasury.
+#[event]
+pub struct ClaimPerformanceFeeEvent {
+    /// Aggregate
+    #[index]
+    pub aggregate: Pubkey,
+
+    /// The [Aggregate::treasury].
+    pub treasury: Pubkey,
+    /// aSOL minted to the treasury.
+    pub fee_asol: u64,
+
+    /// Timestamp of the event.
+    pub timestamp: i64,
+}
+
+/// Emitted when an [Aggregate] and its stake pools are refreshed.
+#[event]
+pub struct RefreshEvent {
+    /// Aggregate
+    #[index]
+    pub aggregate: Pubkey,
+
+    /// The snapshot. It is only recorded on the [Aggregate] if no stake pool was stale.
+    pub snapshot: Snapshot,
+    /// Mints of the stake pools whose exchange rate was stale. Their [crate::StakePool] snapshots were not updated.
+    pub stale_pools: Vec<Pubkey>,
+
+    /// Timestamp of the event.
+    pub timestamp: i64,
+}
+
+/// Emitted when the [ExchangeRateOracle] of an [Aggregate] is updated.
+#[event]
+pub struct ExchangeRateEvent {
+    /// Aggregate
+    #[index]
+    pub aggregate: Pubkey,
+    /// The [ExchangeRateOracle::asol_price].
+    pub asol_price: u64,
+    /// The [ExchangeRateOracle::snapshot_age].
+    pub snapshot_age: i64,
+    /// The [ExchangeRateOracle::is_current].
+    pub is_current: bool,
     /// Timestamp of the event.
     pub timestamp: i64,
 }
@@ -0,0 +1,255 @@
+//! Snapshot history and time-weighted aSOL prices.
+
+use crate::{Snapshot, SnapshotHistory, SnapshotHistoryEntry, SOL};
+use anchor_lang::prelude::*;
+use num_traits::ToPrimitive;
+use vipers::unwrap_int;
+
+/// Maximum number of entries in a [SnapshotHistory].
+pub const SNAPSHOT_HISTORY_LEN: usize = 64;
+
+/// Length in seconds of the periods a [SnapshotHistory] keeps one entry for.
+/// A new entry in the same period as the latest entry replaces it, so that a burst of
+/// transactions cannot flush older entries out of the buffer.
+pub const SNAPSHOT_HISTORY_PERIOD: i64 = 300;
+
+/// A time-weighted average aSOL price. See [SnapshotHistory::twap].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Twap {
+    /// Time-weighted average [SOL] value of 1e9 aSOL.
+    pub price: SOL,
+    /// Number of seconds of the window covered by the history.
+    /// This is less than the requested window if the history does not go back far enough.
+    pub duration: i64,
+}
+
+impl SnapshotHistoryEntry {
+    /// Creates an entry from a [Snapshot].
+    pub fn new(snapshot: &Snapshot, timestamp: i64, slot: u64) -> Self {
+        SnapshotHistoryEntry {
+            timestamp,
+            slot,
+            balance_sol: snapshot.balance_sol,
+            supply: snapshot.supply,
+        }
+    }
+
+    /// The [SOL] value of 1e9 aSOL at this entry, rounded down.
+    pub fn asol_price(&self) -> Result<SOL, ProgramError> {
+        Snapshot {
+            balance_sol: self.balance_sol,
+            supply: self.supply,
+            stake_pools: vec![],
+        }
+        .asol_price()
+    }
+}
+
+impl SnapshotHistory {
+    /// Appends an entry, overwriting the oldest entry once the buffer is full.
+    /// If the latest entry is in the same [SNAPSHOT_HISTORY_PERIOD], it is replaced instead.
+    pub fn append(&mut self, entry: SnapshotHistoryEntry) {
+        let latest_index = self.latest_index();
+        if let Some(latest) = latest_index.and_then(|index| self.entries.get_mut(index)) {
+            if latest.timestamp / SNAPSHOT_HISTORY_PERIOD
+                == entry.timestamp / SNAPSHOT_HISTORY_PERIOD
+            {
+                *latest = entry;
+                return;
+            }
+        }
+        if self.entries.len() < SNAPSHOT_HISTORY_LEN {
+            self.entries.push(entry);
+        } else {
+            self.entries[self.head as usize] = entry;
+            self.head = ((self.head as usize + 1) % SNAPSHOT_HISTORY_LEN) as u16;
+        }
+    }
+
+    /// Appends a [Snapshot] taken at the current [Clock].
+    pub fn record(&mut self, snapshot: &Snapshot) -> ProgramResult {
+        let clock = Clock::get()?;
+        self.append(SnapshotHistoryEntry::new(
+            snapshot,
+            clock.unix_timestamp,
+            clock.slot,
+        ));
+        Ok(())
+    }
+
+    /// The most recently appended entry.
+    pub fn latest(&self) -> Option<&SnapshotHistoryEntry> {
+        self.entries.get(self.latest_index()?)
+    }
+
+    /// Index of the most recently appended entry.
+    fn latest_index(&self) -> Option<usize> {
+        if self.entries.is_empty() {
+            None
+        } else if self.entries.len() < SNAPSHOT_HISTORY_LEN {
+            Some(self.entries.len() - 1)
+        } else {
+            Some((self.head as usize + SNAPSHOT_HISTORY_LEN - 1) % SNAPSHOT_HISTORY_LEN)
+        }
+    }
+
+    /// Iterates over the entries from oldest to newest.
+    pub fn iter(&self) -> impl Iterator<Item = &SnapshotHistoryEntry> {
+        let (newer, older) = self.entries.split_at(self.head as usize);
+        older.iter().chain(newer.iter())
+    }
+
+    /// Computes the time-weighted average aSOL price over the `window` seconds before `now`.
+    /// Each entry's price holds from its timestamp until the next entry, and the latest entry's until `now`.
+    /// Returns [None] if no entry falls within or before the window.
+    pub fn twap(&self, now: i64, window: i64) -> Result<Option<Twap>, ProgramError> {
+        let window_start = unwrap_int!(now.checked_sub(window));
+
+        let mut weighted_sum: u128 = 0;
+        let mut duration: i64 = 0;
+        let mut entries = self.iter().peekable();
+        while let Some(entry) = entries.next() {
+            let end = entries.peek().map_or(now, |next| next.timestamp).min(now);
+            let start = entry.timestamp.max(window_start);
+            if end <= start {
+                continue;
+            }
+            let elapsed = end - start;
+            weighted_sum = unwrap_int!(weighted_sum.checked_add(unwrap_int!(entry
+                .asol_price()?
+                .to_u128()
+                .checked_mul(elapsed as u128))));
+            duration += elapsed;
+        }
+
+        if duration == 0 {
+            return Ok(None);
+        }
+        let price = unwrap_int!(weighted_sum
+            .checked_div(duration as u128)
+            .and_then(|v| v.to_u64()));
+        Ok(Some(Twap {
+            price: SOL::from(price),
+            duration,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ASOL;
+    use anchor_lang::solana_program::native_token::LAMPORTS_PER_SOL;
+
+    /// An entry at the given price, in SOL per 1e9 aSOL.
+    fn entry(timestamp: i64, price: u64) -> SnapshotHistoryEntry {
+        SnapshotHistoryEntry {
+            timestamp,
+            slot: timestamp as u64,
+            balance_sol: SOL::from(price * 100),
+            supply: ASOL::from(LAMPORTS_PER_SOL * 100),
+        }
+    }
+
+    #[test]
+    fn test_append_replaces_within_period() {
+        let mut history = SnapshotHistory::default();
+        history.append(entry(SNAPSHOT_HISTORY_PERIOD, LAMPORTS_PER_SOL));
+        history.append(entry(2 * SNAPSHOT_HISTORY_PERIOD - 1, 2 * LAMPORTS_PER_SOL));
+        assert_eq!(history.entries.len(), 1);
+        assert_eq!(
+            *history.latest().unwrap(),
+            entry(2 * SNAPSHOT_HISTORY_PERIOD - 1, 2 * LAMPORTS_PER_SOL)
+        );
+
+        history.append(entry(2 * SNAPSHOT_HISTORY_PERIOD, 3 * LAMPORTS_PER_SOL));
+        assert_eq!(history.entries.len(), 2);
+        assert_eq!(
+            history.latest().unwrap().timestamp,
+            2 * SNAPSHOT_HISTORY_PERIOD
+        );
+    }
+
+    #[test]
+    fn test_append_wraps_around() {
+        let mut history = SnapshotHistory::default();
+        let total = SNAPSHOT_HISTORY_LEN as i64 + 3;
+        for i in 0..total {
+            history.append(entry(i * SNAPSHOT_HISTORY_PERIOD, LAMPORTS_PER_SOL));
+        }
+        assert_eq!(history.entries.len(), SNAPSHOT_HISTORY_LEN);
+        assert_eq!(history.head, 3);
+        assert_eq!(
+            history.latest().unwrap().timestamp,
+            (total - 1) * SNAPSHOT_HISTORY_PERIOD
+        );
+
+        // iteration is chronological
+        let timestamps: Vec<i64> = history.iter().map(|e| e.timestamp).collect();
+        let expected: Vec<i64> = (3..total).map(|i| i * SNAPSHOT_HISTORY_PERIOD).collect();
+        assert_eq!(timestamps, expected);
+    }
+
+    #[test]
+    fn test_twap_empty() {
+        let history = SnapshotHistory::default();
+        assert_eq!(history.twap(1_000, 500).unwrap(), None);
+    }
+
+    #[test]
+    fn test_twap_weights_by_time() {
+        let mut history = SnapshotHistory::default();
+        history.append(entry(0, LAMPORTS_PER_SOL));
+        history.append(entry(3_000, 2 * LAMPORTS_PER_SOL));
+
+        // 1 SOL for 3000s, 2 SOL for 1000s
+        assert_eq!(
+            history.twap(4_000, 4_000).unwrap(),
+            Some(Twap {
+                price: SOL::from(1_250_000_000),
+                duration: 4_000
+            })
+        );
+
+        // only the last 2000s: 1 SOL for 1000s, 2 SOL for 1000s
+        assert_eq!(
+            history.twap(4_000, 2_000).unwrap(),
+            Some(Twap {
+                price: SOL::from(1_500_000_000),
+                duration: 2_000
+            })
+        );
+    }
+
+    #[test]
+    fn test_twap_partial_coverage() {
+        let mut history = SnapshotHistory::default();
+        history.append(entry(1_000, LAMPORTS_PER_SOL));
+
+        // the history only covers the last 500s of the window
+        assert_eq!(
+            history.twap(1_500, 10_000).unwrap(),
+            Some(Twap {
+                price: SOL::from(LAMPORTS_PER_SOL),
+                duration: 500
+            })
+        );
+    }
+
+    #[test]
+    fn test_twap_resists_spike() {
+        let mut history = SnapshotHistory::default();
+        for i in 0..12 {
+            history.append(entry(i * SNAPSHOT_HISTORY_PERIOD, LAMPORTS_PER_SOL));
+        }
+        // a manipulated price is only recorded for the final interval
+        let spike_ts = 12 * SNAPSHOT_HISTORY_PERIOD;
+        history.append(entry(spike_ts, 10 * LAMPORTS_PER_SOL));
+
+        let twap = history
+            .twap(spike_ts + 1, 12 * SNAPSHOT_HISTORY_PERIOD)
+            .unwrap()
+            .unwrap();
+        assert!(twap.price < SOL::from(LAMPORTS_PER_SOL + LAMPORTS_PER_SOL / 100));
+    }
+}
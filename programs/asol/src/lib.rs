@@ -1,21 +1,41 @@
 //! aSOL: an aggregated Solana stake pool.
+//!
+//! # Upgrading
+//!
+//! The accounts of this version are not compatible with aggregates created by the initial release,
+//! so it must be deployed as a fresh program rather than upgraded in place.
+//! Fields added from now on are appended to the end of each account.
+//!
+//! The crates of those aggregates also have `crate_redeem_in_kind` as their withdraw authority,
+//! whereas [asol::redeem_asol] and [asol::withdraw_single] sign withdrawals as the [Aggregate].
+//! Holders of aSOL from an aggregate of the initial release can redeem it in kind through `crate_redeem_in_kind`,
+//! then deposit the stake pool tokens into a new aggregate with [asol::mint_multi].
 #![deny(rustdoc::all)]
 #![allow(rustdoc::missing_doc_code_examples)]
 
 mod account_validators;
+mod deposit;
+mod mint_multi;
+mod oracle;
 mod pool;
+mod position;
+mod redeem;
+mod referral;
+mod refresh;
 
 pub mod accounting;
 pub mod events;
+pub mod history;
 pub mod snapshot;
 pub mod state;
 pub mod types;
 
 use anchor_lang::{prelude::*, solana_program::native_token::LAMPORTS_PER_SOL};
 use anchor_spl::token::{Mint, Token, TokenAccount};
-use vipers::validate::Validate;
+use vipers::{unwrap_or_err, validate::Validate};
 
 pub use events::*;
+pub use history::*;
 pub use snapshot::*;
 pub use state::*;
 pub use types::*;
@@ -27,9 +47,28 @@ pub const MAX_STAKE_POOLS: usize = 30;
 pub const LAMPORTS_DECIMALS: u8 = 9;
 
 /// The minimum amount of liquidity in the pool for the "exact calculation" of SOL/ASOL price to be used.
-/// If the amount of SOL in the pool is less than this number, the price of ASOL is pegged to 1 SOL.
+/// If no ASOL is outstanding and the amount of SOL in the pool is less than this number, the price of ASOL is pegged to 1 SOL.
+/// Larger balances left without any ASOL outstanding are swept to the treasury, see [Snapshot::compute_performance_fee].
 pub const MIN_LIQUIDITY_FOR_EXACT_CALCULATION: u64 = LAMPORTS_PER_SOL;
 
+/// Maximum [Aggregate::single_withdraw_fee_bps], 10%.
+pub const MAX_SINGLE_WITHDRAW_FEE_BPS: u16 = 1_000;
+
+/// Maximum [crate_token::CrateToken] issue fee, 1%.
+pub const MAX_ISSUE_FEE_BPS: u16 = 100;
+
+/// Maximum [crate_token::CrateToken] withdraw fee, 1%.
+pub const MAX_WITHDRAW_FEE_BPS: u16 = 100;
+
+/// Maximum [Aggregate::performance_fee_bps], 20%.
+pub const MAX_PERFORMANCE_FEE_BPS: u16 = 2_000;
+
+/// [crate_token::CrateToken] withdraw fee of a new [Aggregate], 0.5%.
+pub const DEFAULT_WITHDRAW_FEE_BPS: u16 = 50;
+
+/// Number of basis points in a whole.
+pub const MAX_BPS: u16 = 10_000;
+
 declare_id!("AURUqAcTZP8mhR6sWVxWyfBbpJRj4A3qqeFzLNhrwayE");
 
 pub mod stake_pool_mints {
@@ -48,12 +87,14 @@ pub mod stake_pool_mints {
 pub mod asol {
     use super::*;
 
-    /// Provisions a new aggregate SOL.
+    /// Provisions a new aggregate SOL and its [SnapshotHistory].
+    /// The aggregate is the withdraw authority of its crate, so that it can sign redemptions.
     #[access_control(ctx.accounts.validate())]
     pub fn new_aggregate(
         ctx: Context<NewAggregate>,
         agg_bump: u8,
         crate_bump: u8,
+        history_bump: u8,
     ) -> ProgramResult {
         crate_token::cpi::new_crate(
             CpiContext::new(
@@ -65,7 +106,7 @@ pub mod asol {
                     fee_setter_authority: ctx.accounts.aggregate.to_account_info(),
                     author_fee_to: ctx.accounts.aggregate.to_account_info(),
                     issue_authority: ctx.accounts.aggregate.to_account_info(),
-                    withdraw_authority: ctx.accounts.redeem_in_kind.to_account_info(),
+                    withdraw_authority: ctx.accounts.aggregate.to_account_info(),
                     payer: ctx.accounts.payer.to_account_info(),
                     system_program: ctx.accounts.system_program.to_account_info(),
                 },
@@ -79,7 +120,6 @@ pub mod asol {
             &[agg_bump],
         ]];
 
-        // Withdraw fee is 0.5% or 50 bps
         crate_token::cpi::set_withdraw_fee(
             CpiContext::new(
                 ctx.accounts.crate_token_program.to_account_info(),
@@ -89,7 +129,7 @@ pub mod asol {
                 },
             )
             .with_signer(signer_seeds),
-            50,
+            DEFAULT_WITHDRAW_FEE_BPS,
         )?;
 
         let aggregate = &mut ctx.accounts.aggregate;
@@ -98,18 +138,73 @@ pub mod asol {
 
         aggregate.curator = ctx.accounts.admin.key();
         aggregate.curator_setter = ctx.accounts.admin.key();
+        aggregate.guardian = ctx.accounts.admin.key();
 
+        let snapshot_history = &mut ctx.accounts.snapshot_history;
+        snapshot_history.aggregate = aggregate.key();
+        snapshot_history.bump = history_bump;
+
+        let now = Clock::get()?.unix_timestamp;
         emit!(NewAggregateEvent {
             aggregate: aggregate.key(),
             curator: aggregate.curator,
+            timestamp: now
+        });
+        emit!(NewSnapshotHistoryEvent {
+            aggregate: snapshot_history.aggregate,
+            snapshot_history: snapshot_history.key(),
+            timestamp: now
+        });
+
+        Ok(())
+    }
+
+    /// Creates the [Position] of an owner in an aggregate.
+    /// Anyone may create a position on behalf of an owner.
+    #[access_control(ctx.accounts.validate())]
+    pub fn new_position(ctx: Context<NewPosition>, bump: u8) -> ProgramResult {
+        let position = &mut ctx.accounts.position;
+        position.aggregate = ctx.accounts.aggregate.key();
+        position.owner = ctx.accounts.owner.key();
+        position.bump = bump;
+
+        emit!(NewPositionEvent {
+            aggregate: position.aggregate,
+            owner: position.owner,
+            position: position.key(),
             timestamp: Clock::get()?.unix_timestamp
         });
 
         Ok(())
     }
 
-    /// Adds a new stake pool to an aggregate.
+    /// Creates a [Referrer] of deposits into an aggregate.
+    /// The owner chooses the aSOL account receiving the referral fees.
+    ///
+    /// Anyone may create a referrer. Depositors cannot refer themselves, but nothing prevents
+    /// a depositor from referring their deposits through a referrer owned by a second wallet,
+    /// so the referral fee effectively acts as a discount on the author issue fee.
     #[access_control(ctx.accounts.validate())]
+    pub fn new_referrer(ctx: Context<NewReferrer>, bump: u8) -> ProgramResult {
+        let referrer = &mut ctx.accounts.referrer;
+        referrer.aggregate = ctx.accounts.aggregate.key();
+        referrer.owner = ctx.accounts.owner.key();
+        referrer.bump = bump;
+        referrer.fee_destination = ctx.accounts.fee_destination.key();
+
+        emit!(NewReferrerEvent {
+            aggregate: referrer.aggregate,
+            owner: referrer.owner,
+            referrer: referrer.key(),
+            fee_destination: referrer.fee_destination,
+            timestamp: Clock::get()?.unix_timestamp
+        });
+
+        Ok(())
+    }
+
+    /// Adds a new stake pool to an aggregate.
+    #[access_control(ctx.accounts.validate() ctx.accounts.validate_pool_state(accounting_method))]
     pub fn add_stake_pool(
         ctx: Context<AddStakePool>,
         bump: u8,
@@ -123,6 +218,12 @@ pub mod asol {
         stake_pool.bump = bump;
         stake_pool.accounting_method = accounting_method;
 
+        // no limits by default
+        stake_pool.max_weight_bps = MAX_BPS;
+        stake_pool.deposit_cap = SOL::from(u64::MAX);
+        stake_pool.min_sol_for_1e9 = SOL::from(0);
+        stake_pool.max_sol_for_1e9 = SOL::from(u64::MAX);
+
         stake_pool.stats.total_amount_deposited = 0;
         stake_pool.stats.total_amount_minted = ASOL::from(0);
 
@@ -130,6 +231,11 @@ pub mod asol {
         aggregate.stake_pools.push(StakePoolMeta {
             mint: stake_pool.mint,
             accounting_method,
+            pool_state: ctx.accounts.pool_state.key(),
+            is_retired: false,
+            reserves: 0,
+            max_weight_bps: stake_pool.max_weight_bps,
+            pool_program: *ctx.accounts.pool_state.owner,
         });
 
         emit!(AddStakePoolEvent {
@@ -139,6 +245,7 @@ pub mod asol {
             curator: aggregate.curator,
             mint: stake_pool.mint,
             accounting_method,
+            pool_state: ctx.accounts.pool_state.key(),
 
             timestamp: Clock::get()?.unix_timestamp
         });
@@ -146,209 +253,1622 @@ pub mod asol {
         Ok(())
     }
 
-    /// Sets the curator.
+    /// Retires a stake pool, preventing new deposits.
+    /// The stake pool is still counted in snapshots and may still be redeemed from.
     #[access_control(ctx.accounts.validate())]
-    pub fn set_curator(ctx: Context<SetCurator>) -> ProgramResult {
+    pub fn retire_stake_pool(ctx: Context<RetireStakePool>) -> ProgramResult {
+        let stake_pool = &ctx.accounts.stake_pool;
         let aggregate = &mut ctx.accounts.aggregate;
-        let previous_curator = aggregate.curator;
-        aggregate.curator = ctx.accounts.next_curator.key();
+        let pool = unwrap_or_err!(
+            aggregate.find_stake_pool_mut(stake_pool.mint),
+            PoolNotFoundInSnapshot
+        );
+        pool.is_retired = true;
 
-        emit!(SetCuratorEvent {
+        emit!(RetireStakePoolEvent {
             aggregate: aggregate.key(),
-            previous_curator,
+            stake_pool: stake_pool.key(),
+
             curator: aggregate.curator,
-            curator_setter: aggregate.curator_setter,
+            mint: stake_pool.mint,
+
             timestamp: Clock::get()?.unix_timestamp
         });
 
         Ok(())
     }
 
-    /// Mints aSOL from Lido stSOL.
+    /// Removes a stake pool from an aggregate.
+    /// All stake pool tokens deposited through this program must have been withdrawn.
+    /// Tokens sent directly to the Crate's reserves are withdrawn to the `dust_destination`.
     #[access_control(ctx.accounts.validate())]
-    pub fn mint_lido(ctx: Context<SyncAndMint>, deposit_amount: u64) -> ProgramResult {
-        ctx.accounts.sync_and_mint_lido(deposit_amount)
+    pub fn remove_stake_pool(ctx: Context<RemoveStakePool>) -> ProgramResult {
+        // tokens sent directly to the reserves are not tracked, so they are withdrawn on removal
+        let dust = ctx.accounts.crate_reserves.amount;
+        if dust > 0 {
+            let aggregate = &ctx.accounts.aggregate;
+            let signer_seeds: &[&[&[u8]]] = &[&[
+                b"Aggregate".as_ref(),
+                &aggregate.crate_token.to_bytes(),
+                &[aggregate.bump],
+            ]];
+            crate_token::cpi::withdraw(
+                CpiContext::new_with_signer(
+                    ctx.accounts.crate_token_program.to_account_info(),
+                    crate_token::cpi::accounts::Withdraw {
+                        crate_token: ctx.accounts.crate_token.to_account_info(),
+                        crate_underlying: ctx.accounts.crate_reserves.to_account_info(),
+                        withdraw_authority: aggregate.to_account_info(),
+                        withdraw_destination: ctx.accounts.dust_destination.to_account_info(),
+                        author_fee_destination: ctx
+                            .accounts
+                            .author_fee_destination
+                            .to_account_info(),
+                        protocol_fee_destination: ctx
+                            .accounts
+                            .protocol_fee_destination
+                            .to_account_info(),
+                        token_program: ctx.accounts.token_program.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                dust,
+            )?;
+        }
+
+        let mint = ctx.accounts.stake_pool.mint;
+        let aggregate = &mut ctx.accounts.aggregate;
+        aggregate.stake_pools.retain(|pool| pool.mint != mint);
+
+        emit!(RemoveStakePoolEvent {
+            aggregate: aggregate.key(),
+            stake_pool: ctx.accounts.stake_pool.key(),
+
+            curator: aggregate.curator,
+            mint,
+            dust,
+
+            timestamp: Clock::get()?.unix_timestamp
+        });
+
+        Ok(())
     }
 
-    /// Mints aSOL from Marinade mSOL.
+    /// Sets the deposit limits of a stake pool.
     #[access_control(ctx.accounts.validate())]
-    pub fn mint_marinade(ctx: Context<SyncAndMint>, deposit_amount: u64) -> ProgramResult {
-        ctx.accounts.sync_and_mint_marinade(deposit_amount)
-    }
+    pub fn set_stake_pool_limits(
+        ctx: Context<SetStakePoolLimits>,
+        max_weight_bps: u16,
+        deposit_cap: u64,
+    ) -> ProgramResult {
+        require!(max_weight_bps <= MAX_BPS, MaxWeightTooHigh);
+
+        let stake_pool = &mut ctx.accounts.stake_pool;
+        stake_pool.max_weight_bps = max_weight_bps;
+        stake_pool.deposit_cap = SOL::from(deposit_cap);
+        unwrap_or_err!(
+            ctx.accounts.aggregate.find_stake_pool_mut(stake_pool.mint),
+            PoolNotFoundInSnapshot
+        )
+        .max_weight_bps = max_weight_bps;
+
+        emit!(SetStakePoolLimitsEvent {
+            aggregate: ctx.accounts.aggregate.key(),
+            stake_pool: stake_pool.key(),
+
+            curator: ctx.accounts.aggregate.curator,
+            max_weight_bps,
+            deposit_cap,
 
-    /// Getter that logs the prices of all staked SOLs.
-    pub fn print_aggregate_info(ctx: Context<SyncAndMint>) -> ProgramResult {
-        // ensure not mut
-        let accounts: &SyncAndMint = ctx.accounts;
-        emit!(AggregateInfoEvent {
-            snapshot: accounts.build_snapshot()?,
             timestamp: Clock::get()?.unix_timestamp
         });
+
         Ok(())
     }
-}
 
-// --------------------------------
-// Context Structs
-// --------------------------------
+    /// Sets the band of plausible prices of a stake pool, in SOL per 1e9 stake pool tokens.
+    /// Deposits into and single withdrawals from the stake pool are rejected while its price is outside of the band.
+    #[access_control(ctx.accounts.validate())]
+    pub fn set_stake_pool_price_band(
+        ctx: Context<SetStakePoolLimits>,
+        min_sol_for_1e9: u64,
+        max_sol_for_1e9: u64,
+    ) -> ProgramResult {
+        require!(min_sol_for_1e9 <= max_sol_for_1e9, InvalidPriceBand);
 
-/// Accounts for [asol::new_aggregate].
-#[derive(Accounts)]
-#[instruction(agg_bump: u8)]
-pub struct NewAggregate<'info> {
-    /// Information about the crate.
-    #[account(
-        init,
-        seeds = [
-            b"Aggregate".as_ref(),
-            crate_token.key().to_bytes().as_ref()
-        ],
-        bump = agg_bump,
-        payer = payer,
-        // support up to 30 stake pools for aSOL
-        space = 8 + std::mem::size_of::<Aggregate>() + std::mem::size_of::<StakePool>() * MAX_STAKE_POOLS
-    )]
-    pub aggregate: Account<'info, Aggregate>,
+        let stake_pool = &mut ctx.accounts.stake_pool;
+        stake_pool.min_sol_for_1e9 = SOL::from(min_sol_for_1e9);
+        stake_pool.max_sol_for_1e9 = SOL::from(max_sol_for_1e9);
 
-    /// [Mint] of the [crate_token::CrateToken].
-    pub crate_mint: Account<'info, Mint>,
+        emit!(SetStakePoolPriceBandEvent {
+            aggregate: ctx.accounts.aggregate.key(),
+            stake_pool: stake_pool.key(),
 
-    #[account(mut)]
-    pub crate_token: UncheckedAccount<'info>,
+            curator: ctx.accounts.aggregate.curator,
+            min_sol_for_1e9,
+            max_sol_for_1e9,
 
-    /// Redeem in kind.
-    pub redeem_in_kind: UncheckedAccount<'info>,
+            timestamp: Clock::get()?.unix_timestamp
+        });
 
-    /// Payer of the crate initialization.
-    #[account(mut)]
-    pub payer: Signer<'info>,
+        Ok(())
+    }
 
-    /// The admin, who becomes the curator and the curator setter.
-    pub admin: UncheckedAccount<'info>,
+    /// Proposes a new curator. The proposed curator must call [asol::accept_curator] to become the curator.
+    /// Proposing [Pubkey::default] cancels a pending proposal.
+    #[access_control(ctx.accounts.validate())]
+    pub fn set_curator(ctx: Context<SetCurator>) -> ProgramResult {
+        let aggregate = &mut ctx.accounts.aggregate;
+        aggregate.pending_curator = ctx.accounts.next_curator.key();
 
-    /// System program.
-    pub system_program: Program<'info, System>,
+        emit!(SetPendingCuratorEvent {
+            aggregate: aggregate.key(),
+            curator: aggregate.curator,
+            pending_curator: aggregate.pending_curator,
+            curator_setter: aggregate.curator_setter,
+            timestamp: Clock::get()?.unix_timestamp
+        });
 
-    /// Crate token program.
-    pub crate_token_program: Program<'info, crate_token::program::CrateToken>,
-}
+        Ok(())
+    }
 
-/// Accounts for [asol::add_stake_pool].
-#[derive(Accounts)]
-#[instruction(bump: u8)]
-pub struct AddStakePool<'info> {
-    #[account(mut)]
-    pub aggregate: Account<'info, Aggregate>,
+    /// Accepts the curator role. Must be signed by the [Aggregate::pending_curator].
+    #[access_control(ctx.accounts.validate())]
+    pub fn accept_curator(ctx: Context<AcceptCurator>) -> ProgramResult {
+        let aggregate = &mut ctx.accounts.aggregate;
+        let previous_curator = aggregate.curator;
+        aggregate.curator = aggregate.pending_curator;
+        aggregate.pending_curator = Pubkey::default();
 
-    /// The [StakePool] to add.
-    #[account(
-        init,
-        seeds = [
-            b"StakePool",
-            aggregate.key().to_bytes().as_ref(),
-            mint.key().to_bytes().as_ref()
-        ],
-        bump = bump,
-        payer = payer
-    )]
-    pub stake_pool: Account<'info, StakePool>,
+        emit!(SetCuratorEvent {
+            aggregate: aggregate.key(),
+            previous_curator,
+            curator: aggregate.curator,
+            curator_setter: aggregate.curator_setter,
+            timestamp: Clock::get()?.unix_timestamp
+        });
 
-    /// [Mint] of the stake pool.
-    pub mint: Account<'info, Mint>,
+        Ok(())
+    }
 
-    /// The [Aggregate::curator].
-    pub curator: Signer<'info>,
+    /// Proposes a new curator setter. The proposed curator setter must call [asol::accept_curator_setter] to take over.
+    /// Proposing [Pubkey::default] cancels a pending proposal.
+    #[access_control(ctx.accounts.validate())]
+    pub fn set_curator_setter(ctx: Context<SetCuratorSetter>) -> ProgramResult {
+        let aggregate = &mut ctx.accounts.aggregate;
+        aggregate.pending_curator_setter = ctx.accounts.next_curator_setter.key();
 
-    /// Payer of the crate initialization.
-    #[account(mut)]
-    pub payer: Signer<'info>,
+        emit!(SetPendingCuratorSetterEvent {
+            aggregate: aggregate.key(),
+            curator_setter: aggregate.curator_setter,
+            pending_curator_setter: aggregate.pending_curator_setter,
+            timestamp: Clock::get()?.unix_timestamp
+        });
 
-    /// System program.
-    pub system_program: Program<'info, System>,
-}
+        Ok(())
+    }
 
-/// Accounts for [asol::set_curator].
-#[derive(Accounts)]
-pub struct SetCurator<'info> {
-    /// [Aggregate].
-    #[account(mut)]
-    pub aggregate: Account<'info, Aggregate>,
-    /// The [Aggregate::curator].
-    pub curator_setter: Signer<'info>,
-    /// The [Aggregate::curator] to set.
-    pub next_curator: UncheckedAccount<'info>,
-}
+    /// Accepts the curator setter role. Must be signed by the [Aggregate::pending_curator_setter].
+    #[access_control(ctx.accounts.validate())]
+    pub fn accept_curator_setter(ctx: Context<AcceptCuratorSetter>) -> ProgramResult {
+        let aggregate = &mut ctx.accounts.aggregate;
+        let previous_curator_setter = aggregate.curator_setter;
+        aggregate.curator_setter = aggregate.pending_curator_setter;
+        aggregate.pending_curator_setter = Pubkey::default();
 
-/// Accounts for minting aSOL.
-#[derive(Accounts)]
-pub struct MintASol<'info> {
-    /// Information about the aggregate.
-    #[account(mut)]
-    pub aggregate: Account<'info, Aggregate>,
+        emit!(SetCuratorSetterEvent {
+            aggregate: aggregate.key(),
+            previous_curator_setter,
+            curator_setter: aggregate.curator_setter,
+            timestamp: Clock::get()?.unix_timestamp
+        });
 
-    /// The [StakePool].
-    #[account(mut)]
-    pub stake_pool: Account<'info, StakePool>,
+        Ok(())
+    }
 
-    /// [TokenAccount] holding the [StakePool] tokens of the [crate_token::CrateToken].
-    #[account(mut)]
-    pub stake_pool_tokens: Box<Account<'info, TokenAccount>>,
+    /// Sets the guardian.
+    #[access_control(ctx.accounts.validate())]
+    pub fn set_guardian(ctx: Context<SetGuardian>) -> ProgramResult {
+        let aggregate = &mut ctx.accounts.aggregate;
+        let previous_guardian = aggregate.guardian;
+        aggregate.guardian = ctx.accounts.next_guardian.key();
 
-    /// Information about the crate.
-    pub crate_token: Box<Account<'info, crate_token::CrateToken>>,
+        emit!(SetGuardianEvent {
+            aggregate: aggregate.key(),
+            previous_guardian,
+            guardian: aggregate.guardian,
+            curator_setter: aggregate.curator_setter,
+            timestamp: Clock::get()?.unix_timestamp
+        });
 
-    /// [Mint] of the [crate_token::CrateToken].
-    #[account(mut)]
-    pub crate_mint: Box<Account<'info, Mint>>,
+        Ok(())
+    }
 
-    /// The depositor into the pool.
-    #[account(mut)]
-    pub depositor: Signer<'info>,
+    /// Pauses operations on the aggregate.
+    #[access_control(ctx.accounts.validate())]
+    pub fn pause(ctx: Context<SetPaused>, flags: u8) -> ProgramResult {
+        require!(flags & !AGGREGATE_PAUSE_FLAGS == 0, InvalidPauseFlags);
+        let aggregate = &mut ctx.accounts.aggregate;
+        aggregate.paused |= flags;
 
-    /// The source of the deposited [StakePool] tokens.
-    #[account(mut)]
-    pub depositor_source: Box<Account<'info, TokenAccount>>,
+        emit!(PauseEvent {
+            aggregate: aggregate.key(),
+            stake_pool: None,
+            guardian: aggregate.guardian,
+            flags,
+            paused: aggregate.paused,
+            timestamp: Clock::get()?.unix_timestamp
+        });
 
-    /// Destination of the issued tokens.
-    #[account(mut)]
-    pub mint_destination: Box<Account<'info, TokenAccount>>,
+        Ok(())
+    }
 
-    /// [Token] program.
-    pub token_program: Program<'info, Token>,
+    /// Unpauses operations on the aggregate.
+    #[access_control(ctx.accounts.validate())]
+    pub fn unpause(ctx: Context<SetPaused>, flags: u8) -> ProgramResult {
+        require!(flags & !AGGREGATE_PAUSE_FLAGS == 0, InvalidPauseFlags);
+        let aggregate = &mut ctx.accounts.aggregate;
+        aggregate.paused &= !flags;
 
-    /// [crate_token::program::CrateToken] program.
-    pub crate_token_program: Program<'info, crate_token::program::CrateToken>,
-}
+        emit!(UnpauseEvent {
+            aggregate: aggregate.key(),
+            stake_pool: None,
+            guardian: aggregate.guardian,
+            flags,
+            paused: aggregate.paused,
+            timestamp: Clock::get()?.unix_timestamp
+        });
 
-#[derive(Accounts)]
-pub struct SyncAndMint<'info> {
-    /// Mint aSOL
-    pub mint_asol: MintASol<'info>,
-    /// Sync accounts
-    pub sync: SyncAll<'info>,
-}
+        Ok(())
+    }
 
-/// Accounts for synchronization.
-/// TODO: this should be generic
-#[derive(Accounts)]
-pub struct SyncAll<'info> {
-    /// Marinade accounts.
-    pub marinade: SyncMarinade<'info>,
+    /// Pauses operations on a stake pool.
+    #[access_control(ctx.accounts.validate())]
+    pub fn pause_stake_pool(ctx: Context<SetStakePoolPaused>, flags: u8) -> ProgramResult {
+        require!(flags & !STAKE_POOL_PAUSE_FLAGS == 0, InvalidPauseFlags);
+        let stake_pool = &mut ctx.accounts.stake_pool;
+        stake_pool.paused |= flags;
+
+        emit!(PauseEvent {
+            aggregate: ctx.accounts.aggregate.key(),
+            stake_pool: Some(stake_pool.key()),
+            guardian: ctx.accounts.aggregate.guardian,
+            flags,
+            paused: stake_pool.paused,
+            timestamp: Clock::get()?.unix_timestamp
+        });
 
-    /// Lido accounts.
-    pub lido: SyncLido<'info>,
-}
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct SyncMarinade<'info> {
-    /// [marinade] state account.
-    pub marinade: Box<Account<'info, marinade::State>>,
+    /// Unpauses operations on a stake pool.
+    #[access_control(ctx.accounts.validate())]
+    pub fn unpause_stake_pool(ctx: Context<SetStakePoolPaused>, flags: u8) -> ProgramResult {
+        require!(flags & !STAKE_POOL_PAUSE_FLAGS == 0, InvalidPauseFlags);
+        let stake_pool = &mut ctx.accounts.stake_pool;
+        stake_pool.paused &= !flags;
+
+        emit!(UnpauseEvent {
+            aggregate: ctx.accounts.aggregate.key(),
+            stake_pool: Some(stake_pool.key()),
+            guardian: ctx.accounts.aggregate.guardian,
+            flags,
+            paused: stake_pool.paused,
+            timestamp: Clock::get()?.unix_timestamp
+        });
 
-    /// [TokenAccount] holding the tokens of the [StakePool].
-    pub marinade_stake_pool_tokens: Box<Account<'info, TokenAccount>>,
-}
+        Ok(())
+    }
 
-#[derive(Accounts)]
+    /// Mints aSOL from Lido stSOL.
+    #[access_control(ctx.accounts.validate())]
+    pub fn mint_lido<'info>(
+        ctx: Context<'_, '_, '_, 'info, SyncAndMint<'info>>,
+        deposit_amount: u64,
+        min_mint_amount: u64,
+        options: MintOptions,
+    ) -> ProgramResult {
+        ctx.accounts.sync_and_mint(
+            ctx.remaining_accounts,
+            AccountingMethod::Lido,
+            deposit_amount,
+            min_mint_amount,
+            options,
+        )
+    }
+
+    /// Mints aSOL from Marinade mSOL.
+    #[access_control(ctx.accounts.validate())]
+    pub fn mint_marinade<'info>(
+        ctx: Context<'_, '_, '_, 'info, SyncAndMint<'info>>,
+        deposit_amount: u64,
+        min_mint_amount: u64,
+        options: MintOptions,
+    ) -> ProgramResult {
+        ctx.accounts.sync_and_mint(
+            ctx.remaining_accounts,
+            AccountingMethod::Marinade,
+            deposit_amount,
+            min_mint_amount,
+            options,
+        )
+    }
+
+    /// Mints aSOL from the tokens of an SPL stake pool.
+    #[access_control(ctx.accounts.validate())]
+    pub fn mint_spl_stake_pool<'info>(
+        ctx: Context<'_, '_, '_, 'info, SyncAndMint<'info>>,
+        deposit_amount: u64,
+        min_mint_amount: u64,
+        options: MintOptions,
+    ) -> ProgramResult {
+        ctx.accounts.sync_and_mint(
+            ctx.remaining_accounts,
+            AccountingMethod::SplStakePool,
+            deposit_amount,
+            min_mint_amount,
+            options,
+        )
+    }
+
+    /// Mints aSOL from the tokens of several stake pools at once.
+    /// Every deposit is priced against the same snapshot, and the aSOL is issued once.
+    /// Fails if fewer than `min_mint_amount` aSOL would be received in total.
+    /// Remaining accounts must contain the [SyncAll] accounts, followed by
+    /// one [MintMultiPool] per deposit, in order, and the [MintOptionalAccounts].
+    #[access_control(ctx.accounts.validate())]
+    pub fn mint_multi<'info>(
+        ctx: Context<'_, '_, '_, 'info, MintMulti<'info>>,
+        deposits: Vec<MintDeposit>,
+        min_mint_amount: u64,
+        options: MintOptions,
+    ) -> ProgramResult {
+        ctx.accounts
+            .mint_multi(ctx.remaining_accounts, deposits, min_mint_amount, options)
+    }
+
+    /// Deposits SOL into the stake pool furthest below its max weight, then mints aSOL.
+    /// The [MintASol::stake_pool] must be the eligible stake pool with the most headroom, see [Snapshot::find_pool_with_most_headroom].
+    /// Remaining accounts must contain the [SyncAll] accounts, followed by
+    /// the [DepositSolMarinade] or [DepositSolLido] accounts of that stake pool,
+    /// and the [MintOptionalAccounts].
+    #[access_control(ctx.accounts.validate())]
+    pub fn deposit_sol<'info>(
+        ctx: Context<'_, '_, '_, 'info, DepositSol<'info>>,
+        lamports: u64,
+        min_mint_amount: u64,
+        options: MintOptions,
+    ) -> ProgramResult {
+        ctx.accounts
+            .deposit_sol(ctx.remaining_accounts, lamports, min_mint_amount, options)
+    }
+
+    /// Deposits a stake account into a Marinade or SPL stake pool, then mints aSOL.
+    /// The `validator_index` is the index of the stake account's validator in the Marinade validator list,
+    /// and is ignored for SPL stake pools.
+    /// Remaining accounts must contain the [SyncAll] accounts, followed by
+    /// the [DepositStakeMarinade] or [DepositStakeSplStakePool] accounts of the [MintASol::stake_pool],
+    /// and the [MintOptionalAccounts].
+    #[access_control(ctx.accounts.validate())]
+    pub fn deposit_stake_account<'info>(
+        ctx: Context<'_, '_, '_, 'info, DepositStakeAccount<'info>>,
+        validator_index: u32,
+        min_mint_amount: u64,
+        options: MintOptions,
+    ) -> ProgramResult {
+        ctx.accounts.deposit_stake_account(
+            ctx.remaining_accounts,
+            validator_index,
+            min_mint_amount,
+            options,
+        )
+    }
+
+    /// Redeems aSOL for a pro-rata share of every stake pool token in the aggregate.
+    /// Remaining accounts must contain the [SyncAll] accounts, followed by
+    /// one [RedeemASolPool] per [Aggregate::stake_pools] entry, in order,
+    /// and optionally the redeemer's [Position].
+    #[access_control(ctx.accounts.validate())]
+    pub fn redeem_asol<'info>(
+        ctx: Context<'_, '_, '_, 'info, SyncAndRedeem<'info>>,
+        redeem_amount: u64,
+    ) -> ProgramResult {
+        ctx.accounts
+            .sync_and_redeem(ctx.remaining_accounts, redeem_amount)
+    }
+
+    /// Withdraws aSOL for the tokens of a single stake pool.
+    /// Remaining accounts must contain the [SyncAll] accounts, optionally followed by the redeemer's [Position].
+    #[access_control(ctx.accounts.validate())]
+    pub fn withdraw_single<'info>(
+        ctx: Context<'_, '_, '_, 'info, SyncAndWithdrawSingle<'info>>,
+        redeem_amount: u64,
+    ) -> ProgramResult {
+        ctx.accounts
+            .sync_and_withdraw_single(ctx.remaining_accounts, redeem_amount)
+    }
+
+    /// Sets the issue and withdraw fees of the [crate_token::CrateToken].
+    /// A non-zero issue fee requires the fee destinations to be set via [asol::set_fee_destinations].
+    #[access_control(ctx.accounts.validate())]
+    pub fn set_fees(
+        ctx: Context<SetFees>,
+        issue_fee_bps: u16,
+        withdraw_fee_bps: u16,
+    ) -> ProgramResult {
+        require!(issue_fee_bps <= MAX_ISSUE_FEE_BPS, IssueFeeTooHigh);
+        require!(withdraw_fee_bps <= MAX_WITHDRAW_FEE_BPS, WithdrawFeeTooHigh);
+
+        let aggregate = &ctx.accounts.aggregate;
+        require!(
+            issue_fee_bps == 0 || aggregate.has_fee_destinations(),
+            FeeDestinationsNotSet
+        );
+
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"Aggregate".as_ref(),
+            &aggregate.crate_token.to_bytes(),
+            &[aggregate.bump],
+        ]];
+        let previous_issue_fee_bps = ctx.accounts.crate_token.issue_fee_bps;
+        let previous_withdraw_fee_bps = ctx.accounts.crate_token.withdraw_fee_bps;
+
+        crate_token::cpi::set_issue_fee(
+            CpiContext::new_with_signer(
+                ctx.accounts.crate_token_program.to_account_info(),
+                crate_token::cpi::accounts::SetFees {
+                    crate_token: ctx.accounts.crate_token.to_account_info(),
+                    fee_setter: aggregate.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            issue_fee_bps,
+        )?;
+        crate_token::cpi::set_withdraw_fee(
+            CpiContext::new_with_signer(
+                ctx.accounts.crate_token_program.to_account_info(),
+                crate_token::cpi::accounts::SetFees {
+                    crate_token: ctx.accounts.crate_token.to_account_info(),
+                    fee_setter: aggregate.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            withdraw_fee_bps,
+        )?;
+
+        emit!(SetFeesEvent {
+            aggregate: aggregate.key(),
+            curator: aggregate.curator,
+            issue_fee_bps,
+            previous_issue_fee_bps,
+            withdraw_fee_bps,
+            previous_withdraw_fee_bps,
+            timestamp: Clock::get()?.unix_timestamp
+        });
+
+        Ok(())
+    }
+
+    /// Sets the aSOL [TokenAccount]s receiving the issue fees of the [crate_token::CrateToken].
+    #[access_control(ctx.accounts.validate())]
+    pub fn set_fee_destinations(ctx: Context<SetFeeDestinations>) -> ProgramResult {
+        let aggregate = &mut ctx.accounts.aggregate;
+        aggregate.author_fee_destination = ctx.accounts.author_fee_destination.key();
+        aggregate.protocol_fee_destination = ctx.accounts.protocol_fee_destination.key();
+
+        emit!(SetFeeDestinationsEvent {
+            aggregate: aggregate.key(),
+            curator: aggregate.curator,
+            author_fee_destination: aggregate.author_fee_destination,
+            protocol_fee_destination: aggregate.protocol_fee_destination,
+            timestamp: Clock::get()?.unix_timestamp
+        });
+
+        Ok(())
+    }
+
+    /// Transfers the balance of a fee account owned by the [Aggregate] to a recipient chosen by the curator.
+    /// The [crate_token::CrateToken] pays its author fees to accounts owned by the [Aggregate].
+    #[access_control(ctx.accounts.validate())]
+    pub fn sweep_fees(ctx: Context<SweepFees>) -> ProgramResult {
+        let amount = ctx.accounts.fee_source.amount;
+        require!(amount > 0, ZeroSweep);
+
+        let aggregate = &ctx.accounts.aggregate;
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"Aggregate".as_ref(),
+            &aggregate.crate_token.to_bytes(),
+            &[aggregate.bump],
+        ]];
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                anchor_spl::token::Transfer {
+                    from: ctx.accounts.fee_source.to_account_info(),
+                    to: ctx.accounts.fee_recipient.to_account_info(),
+                    authority: aggregate.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            amount,
+        )?;
+
+        emit!(SweepFeesEvent {
+            aggregate: aggregate.key(),
+            curator: aggregate.curator,
+            mint: ctx.accounts.fee_source.mint,
+            fee_source: ctx.accounts.fee_source.key(),
+            fee_recipient: ctx.accounts.fee_recipient.key(),
+            amount,
+            timestamp: Clock::get()?.unix_timestamp
+        });
+
+        Ok(())
+    }
+
+    /// Sets the share of the author issue fee paid to the [Referrer] of a deposit.
+    /// Since depositors may refer themselves through a second wallet, see [asol::new_referrer],
+    /// this is the largest discount on the author issue fee any depositor can get.
+    #[access_control(ctx.accounts.validate())]
+    pub fn set_referral_fee(ctx: Context<SetReferralFee>, referral_fee_bps: u16) -> ProgramResult {
+        require!(referral_fee_bps <= MAX_BPS, ReferralFeeTooHigh);
+
+        let aggregate = &mut ctx.accounts.aggregate;
+        let previous_referral_fee_bps = aggregate.referral_fee_bps;
+        aggregate.referral_fee_bps = referral_fee_bps;
+
+        emit!(SetReferralFeeEvent {
+            aggregate: aggregate.key(),
+            curator: aggregate.curator,
+            referral_fee_bps,
+            previous_referral_fee_bps,
+            timestamp: Clock::get()?.unix_timestamp
+        });
+
+        Ok(())
+    }
+
+    /// Sets the performance fee and the treasury receiving it.
+    /// The new fee applies to all yield not yet accrued, i.e. earned since the last sync.
+    #[access_control(ctx.accounts.validate())]
+    pub fn set_performance_fee(
+        ctx: Context<SetPerformanceFee>,
+        performance_fee_bps: u16,
+    ) -> ProgramResult {
+        require!(
+            performance_fee_bps <= MAX_PERFORMANCE_FEE_BPS,
+            PerformanceFeeTooHigh
+        );
+
+        let aggregate = &mut ctx.accounts.aggregate;
+        let previous_performance_fee_bps = aggregate.performance_fee_bps;
+        aggregate.performance_fee_bps = performance_fee_bps;
+        aggregate.treasury = ctx.accounts.treasury.key();
+
+        emit!(SetPerformanceFeeEvent {
+            aggregate: aggregate.key(),
+            curator: aggregate.curator,
+            performance_fee_bps,
+            previous_performance_fee_bps,
+            treasury: aggregate.treasury,
+            timestamp: Clock::get()?.unix_timestamp
+        });
+
+        Ok(())
+    }
+
+    /// Mints the accrued performance fee to the [Aggregate::treasury]. Anyone may call this.
+    /// The [crate_token::CrateToken] issue fee is not charged on the performance fee:
+    /// it is set to zero for the mint and restored afterwards.
+    #[access_control(ctx.accounts.validate())]
+    pub fn claim_performance_fee(ctx: Context<ClaimPerformanceFee>) -> ProgramResult {
+        let fee_asol = ctx.accounts.aggregate.pending_performance_fee;
+        if fee_asol.amount == 0 {
+            return Ok(());
+        }
+
+        let aggregate = &ctx.accounts.aggregate;
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"Aggregate".as_ref(),
+            &aggregate.crate_token.to_bytes(),
+            &[aggregate.bump],
+        ]];
+        let set_issue_fee = |issue_fee_bps: u16| {
+            crate_token::cpi::set_issue_fee(
+                CpiContext::new_with_signer(
+                    ctx.accounts.crate_token_program.to_account_info(),
+                    crate_token::cpi::accounts::SetFees {
+                        crate_token: ctx.accounts.crate_token.to_account_info(),
+                        fee_setter: aggregate.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                issue_fee_bps,
+            )
+        };
+
+        let issue_fee_bps = ctx.accounts.crate_token.issue_fee_bps;
+        if issue_fee_bps != 0 {
+            set_issue_fee(0)?;
+        }
+        crate_token::cpi::issue(
+            CpiContext::new_with_signer(
+                ctx.accounts.crate_token_program.to_account_info(),
+                crate_token::cpi::accounts::Issue {
+                    crate_token: ctx.accounts.crate_token.to_account_info(),
+                    crate_mint: ctx.accounts.crate_mint.to_account_info(),
+                    issue_authority: aggregate.to_account_info(),
+                    mint_destination: ctx.accounts.treasury.to_account_info(),
+                    author_fee_destination: ctx.accounts.author_fee_destination.to_account_info(),
+                    protocol_fee_destination: ctx
+                        .accounts
+                        .protocol_fee_destination
+                        .to_account_info(),
+                    token_program: ctx.accounts.token_program.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            fee_asol.amount,
+        )?;
+        if issue_fee_bps != 0 {
+            set_issue_fee(issue_fee_bps)?;
+        }
+
+        let aggregate = &mut ctx.accounts.aggregate;
+        aggregate.pending_performance_fee = ASOL::from(0);
+
+        emit!(ClaimPerformanceFeeEvent {
+            aggregate: aggregate.key(),
+            treasury: aggregate.treasury,
+            fee_asol: fee_asol.amount,
+            timestamp: Clock::get()?.unix_timestamp
+        });
+
+        Ok(())
+    }
+
+    /// Sets the fee charged on [asol::withdraw_single].
+    #[access_control(ctx.accounts.validate())]
+    pub fn set_single_withdraw_fee(
+        ctx: Context<SetSingleWithdrawFee>,
+        single_withdraw_fee_bps: u16,
+    ) -> ProgramResult {
+        require!(
+            single_withdraw_fee_bps <= MAX_SINGLE_WITHDRAW_FEE_BPS,
+            SingleWithdrawFeeTooHigh
+        );
+
+        let aggregate = &mut ctx.accounts.aggregate;
+        let previous_single_withdraw_fee_bps = aggregate.single_withdraw_fee_bps;
+        aggregate.single_withdraw_fee_bps = single_withdraw_fee_bps;
+
+        emit!(SetSingleWithdrawFeeEvent {
+            aggregate: aggregate.key(),
+            curator: aggregate.curator,
+            single_withdraw_fee_bps,
+            previous_single_withdraw_fee_bps,
+            timestamp: Clock::get()?.unix_timestamp
+        });
+
+        Ok(())
+    }
+
+    /// Records a fresh snapshot on the [Aggregate] and every [StakePool]. Anyone may call this.
+    /// Stake pools with a stale exchange rate are skipped. Until all are fresh, no performance fee is accrued
+    /// and the aggregate snapshot and its [SnapshotHistory] are left unchanged.
+    /// The remaining accounts are the sync accounts followed by every [StakePool] of the aggregate, in order.
+    #[access_control(ctx.accounts.validate())]
+    pub fn refresh<'info>(ctx: Context<'_, '_, '_, 'info, Refresh<'info>>) -> ProgramResult {
+        ctx.accounts.refresh(ctx.remaining_accounts)
+    }
+
+    /// Creates the [ExchangeRateOracle] of an aggregate.
+    #[access_control(ctx.accounts.validate())]
+    pub fn new_exchange_rate_oracle(
+        ctx: Context<NewExchangeRateOracle>,
+        bump: u8,
+    ) -> ProgramResult {
+        let oracle = &mut ctx.accounts.exchange_rate_oracle;
+        oracle.aggregate = ctx.accounts.aggregate.key();
+        oracle.bump = bump;
+
+        emit!(NewExchangeRateOracleEvent {
+            aggregate: oracle.aggregate,
+            exchange_rate_oracle: oracle.key(),
+            timestamp: Clock::get()?.unix_timestamp
+        });
+
+        Ok(())
+    }
+
+    /// Computes the SOL value of aSOL and writes it to the [ExchangeRateOracle]. Anyone may call this.
+    /// The aggregate is not modified. Other programs may call this via the `cpi` feature,
+    /// passing the sync accounts as remaining accounts, then read the [ExchangeRateOracle].
+    #[access_control(ctx.accounts.validate())]
+    pub fn get_exchange_rate<'info>(
+        ctx: Context<'_, '_, '_, 'info, GetExchangeRate<'info>>,
+    ) -> ProgramResult {
+        ctx.accounts.get_exchange_rate(ctx.remaining_accounts)
+    }
+
+    /// Getter that logs the prices of all staked SOLs.
+    pub fn print_aggregate_info<'info>(
+        ctx: Context<'_, '_, '_, 'info, SyncAndMint<'info>>,
+    ) -> ProgramResult {
+        // ensure not mut
+        let accounts: &SyncAndMint = ctx.accounts;
+        let (sync, _) = SyncAll::load(&accounts.mint_asol.aggregate, ctx.remaining_accounts)?;
+        emit!(AggregateInfoEvent {
+            snapshot: sync.build_snapshot(
+                &accounts.mint_asol.aggregate,
+                &accounts.mint_asol.crate_mint
+            )?,
+            timestamp: Clock::get()?.unix_timestamp
+        });
+        Ok(())
+    }
+}
+
+// --------------------------------
+// Context Structs
+// --------------------------------
+
+/// Accounts for [asol::new_aggregate].
+#[derive(Accounts)]
+#[instruction(agg_bump: u8, crate_bump: u8, history_bump: u8)]
+pub struct NewAggregate<'info> {
+    /// Information about the crate.
+    #[account(
+        init,
+        seeds = [
+            b"Aggregate".as_ref(),
+            crate_token.key().to_bytes().as_ref()
+        ],
+        bump = agg_bump,
+        payer = payer,
+        // support up to 30 stake pools for aSOL
+        space = 8 + std::mem::size_of::<Aggregate>() + std::mem::size_of::<StakePool>() * MAX_STAKE_POOLS
+    )]
+    pub aggregate: Account<'info, Aggregate>,
+
+    /// The [SnapshotHistory] of the aggregate.
+    #[account(
+        init,
+        seeds = [
+            b"SnapshotHistory",
+            aggregate.key().to_bytes().as_ref()
+        ],
+        bump = history_bump,
+        payer = payer,
+        space = 8 + std::mem::size_of::<SnapshotHistory>() + std::mem::size_of::<SnapshotHistoryEntry>() * SNAPSHOT_HISTORY_LEN
+    )]
+    pub snapshot_history: Box<Account<'info, SnapshotHistory>>,
+
+    /// [Mint] of the [crate_token::CrateToken].
+    pub crate_mint: Account<'info, Mint>,
+
+    #[account(mut)]
+    pub crate_token: UncheckedAccount<'info>,
+
+    /// Payer of the crate initialization.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// The admin, who becomes the curator and the curator setter.
+    pub admin: UncheckedAccount<'info>,
+
+    /// System program.
+    pub system_program: Program<'info, System>,
+
+    /// Crate token program.
+    pub crate_token_program: Program<'info, crate_token::program::CrateToken>,
+}
+
+/// Accounts for [asol::new_position].
+#[derive(Accounts)]
+#[instruction(bump: u8)]
+pub struct NewPosition<'info> {
+    pub aggregate: Account<'info, Aggregate>,
+
+    /// The [Position] to create.
+    #[account(
+        init,
+        seeds = [
+            b"Position",
+            aggregate.key().to_bytes().as_ref(),
+            owner.key().to_bytes().as_ref()
+        ],
+        bump = bump,
+        payer = payer
+    )]
+    pub position: Account<'info, Position>,
+
+    /// The owner of the [Position].
+    pub owner: UncheckedAccount<'info>,
+
+    /// Payer of the position initialization.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// System program.
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts for [asol::new_referrer].
+#[derive(Accounts)]
+#[instruction(bump: u8)]
+pub struct NewReferrer<'info> {
+    pub aggregate: Account<'info, Aggregate>,
+
+    /// Information about the crate.
+    pub crate_token: Box<Account<'info, crate_token::CrateToken>>,
+
+    /// The [Referrer] to create.
+    #[account(
+        init,
+        seeds = [
+            b"Referrer",
+            aggregate.key().to_bytes().as_ref(),
+            owner.key().to_bytes().as_ref()
+        ],
+        bump = bump,
+        payer = payer
+    )]
+    pub referrer: Account<'info, Referrer>,
+
+    /// The owner of the [Referrer].
+    pub owner: Signer<'info>,
+
+    /// The [Referrer::fee_destination].
+    pub fee_destination: Box<Account<'info, TokenAccount>>,
+
+    /// Payer of the referrer initialization.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// System program.
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts for [asol::add_stake_pool].
+#[derive(Accounts)]
+#[instruction(bump: u8)]
+pub struct AddStakePool<'info> {
+    #[account(mut)]
+    pub aggregate: Account<'info, Aggregate>,
+
+    /// The [StakePool] to add.
+    #[account(
+        init,
+        seeds = [
+            b"StakePool",
+            aggregate.key().to_bytes().as_ref(),
+            mint.key().to_bytes().as_ref()
+        ],
+        bump = bump,
+        payer = payer
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+
+    /// [Mint] of the stake pool.
+    pub mint: Account<'info, Mint>,
+
+    /// The account holding the state of the stake pool.
+    /// Its owner is recorded as the [StakePoolMeta::pool_program].
+    pub pool_state: UncheckedAccount<'info>,
+
+    /// The [Aggregate::curator].
+    pub curator: Signer<'info>,
+
+    /// Payer of the crate initialization.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// System program.
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts for [asol::retire_stake_pool].
+#[derive(Accounts)]
+pub struct RetireStakePool<'info> {
+    /// [Aggregate].
+    #[account(mut)]
+    pub aggregate: Account<'info, Aggregate>,
+
+    /// The [StakePool] to retire.
+    pub stake_pool: Account<'info, StakePool>,
+
+    /// The [Aggregate::curator].
+    pub curator: Signer<'info>,
+}
+
+/// Accounts for [asol::remove_stake_pool].
+#[derive(Accounts)]
+pub struct RemoveStakePool<'info> {
+    /// [Aggregate].
+    #[account(mut)]
+    pub aggregate: Account<'info, Aggregate>,
+
+    /// The [StakePool] to remove.
+    #[account(mut, close = curator)]
+    pub stake_pool: Account<'info, StakePool>,
+
+    /// The [crate_token::CrateToken].
+    pub crate_token: Box<Account<'info, crate_token::CrateToken>>,
+
+    /// [TokenAccount] holding the [StakePool] tokens of the [crate_token::CrateToken].
+    #[account(mut)]
+    pub crate_reserves: Box<Account<'info, TokenAccount>>,
+
+    /// Destination of the untracked [StakePool] tokens left in the reserves.
+    #[account(mut)]
+    pub dust_destination: Box<Account<'info, TokenAccount>>,
+
+    /// Destination of the author fee tokens.
+    #[account(mut)]
+    pub author_fee_destination: Box<Account<'info, TokenAccount>>,
+
+    /// Destination of the protocol fee tokens.
+    #[account(mut)]
+    pub protocol_fee_destination: Box<Account<'info, TokenAccount>>,
+
+    /// The [Aggregate::curator]. Receives the rent of the [StakePool].
+    #[account(mut)]
+    pub curator: Signer<'info>,
+
+    /// [Token] program.
+    pub token_program: Program<'info, Token>,
+
+    /// [crate_token::program::CrateToken] program.
+    pub crate_token_program: Program<'info, crate_token::program::CrateToken>,
+}
+
+/// Accounts for [asol::set_stake_pool_limits] and [asol::set_stake_pool_price_band].
+#[derive(Accounts)]
+pub struct SetStakePoolLimits<'info> {
+    /// [Aggregate].
+    #[account(mut)]
+    pub aggregate: Account<'info, Aggregate>,
+
+    /// The [StakePool] to set the limits of.
+    #[account(mut)]
+    pub stake_pool: Account<'info, StakePool>,
+
+    /// The [Aggregate::curator].
+    pub curator: Signer<'info>,
+}
+
+/// Accounts for [asol::set_curator].
+#[derive(Accounts)]
+pub struct SetCurator<'info> {
+    /// [Aggregate].
+    #[account(mut)]
+    pub aggregate: Account<'info, Aggregate>,
+    /// The [Aggregate::curator_setter].
+    pub curator_setter: Signer<'info>,
+    /// The [Aggregate::pending_curator] to set.
+    pub next_curator: UncheckedAccount<'info>,
+}
+
+/// Accounts for [asol::accept_curator].
+#[derive(Accounts)]
+pub struct AcceptCurator<'info> {
+    /// [Aggregate].
+    #[account(mut)]
+    pub aggregate: Account<'info, Aggregate>,
+    /// The [Aggregate::pending_curator].
+    pub pending_curator: Signer<'info>,
+}
+
+/// Accounts for [asol::set_curator_setter].
+#[derive(Accounts)]
+pub struct SetCuratorSetter<'info> {
+    /// [Aggregate].
+    #[account(mut)]
+    pub aggregate: Account<'info, Aggregate>,
+    /// The [Aggregate::curator_setter].
+    pub curator_setter: Signer<'info>,
+    /// The [Aggregate::pending_curator_setter] to set.
+    pub next_curator_setter: UncheckedAccount<'info>,
+}
+
+/// Accounts for [asol::accept_curator_setter].
+#[derive(Accounts)]
+pub struct AcceptCuratorSetter<'info> {
+    /// [Aggregate].
+    #[account(mut)]
+    pub aggregate: Account<'info, Aggregate>,
+    /// The [Aggregate::pending_curator_setter].
+    pub pending_curator_setter: Signer<'info>,
+}
+
+/// Accounts for [asol::set_guardian].
+#[derive(Accounts)]
+pub struct SetGuardian<'info> {
+    /// [Aggregate].
+    #[account(mut)]
+    pub aggregate: Account<'info, Aggregate>,
+    /// The [Aggregate::curator_setter].
+    pub curator_setter: Signer<'info>,
+    /// The [Aggregate::guardian] to set.
+    pub next_guardian: UncheckedAccount<'info>,
+}
+
+/// Accounts for [asol::pause] and [asol::unpause].
+#[derive(Accounts)]
+pub struct SetPaused<'info> {
+    /// [Aggregate].
+    #[account(mut)]
+    pub aggregate: Account<'info, Aggregate>,
+    /// The [Aggregate::guardian].
+    pub guardian: Signer<'info>,
+}
+
+/// Accounts for [asol::pause_stake_pool] and [asol::unpause_stake_pool].
+#[derive(Accounts)]
+pub struct SetStakePoolPaused<'info> {
+    /// [Aggregate].
+    pub aggregate: Account<'info, Aggregate>,
+    /// The [StakePool] to pause or unpause.
+    #[account(mut)]
+    pub stake_pool: Account<'info, StakePool>,
+    /// The [Aggregate::guardian].
+    pub guardian: Signer<'info>,
+}
+
+/// Accounts for [asol::set_fees].
+#[derive(Accounts)]
+pub struct SetFees<'info> {
+    /// [Aggregate].
+    pub aggregate: Account<'info, Aggregate>,
+    /// The [Aggregate::curator].
+    pub curator: Signer<'info>,
+    /// The [crate_token::CrateToken] to set the fees of.
+    #[account(mut)]
+    pub crate_token: Account<'info, crate_token::CrateToken>,
+    /// [crate_token::program::CrateToken] program.
+    pub crate_token_program: Program<'info, crate_token::program::CrateToken>,
+}
+
+/// Accounts for [asol::set_fee_destinations].
+#[derive(Accounts)]
+pub struct SetFeeDestinations<'info> {
+    /// [Aggregate].
+    #[account(mut)]
+    pub aggregate: Account<'info, Aggregate>,
+    /// The [Aggregate::curator].
+    pub curator: Signer<'info>,
+    /// The [crate_token::CrateToken].
+    pub crate_token: Account<'info, crate_token::CrateToken>,
+    /// The [Aggregate::author_fee_destination] to set.
+    pub author_fee_destination: Account<'info, TokenAccount>,
+    /// The [Aggregate::protocol_fee_destination] to set.
+    pub protocol_fee_destination: Account<'info, TokenAccount>,
+}
+
+/// Accounts for [asol::sweep_fees].
+#[derive(Accounts)]
+pub struct SweepFees<'info> {
+    /// [Aggregate].
+    pub aggregate: Account<'info, Aggregate>,
+    /// The [Aggregate::curator].
+    pub curator: Signer<'info>,
+    /// Fee account owned by the [Aggregate].
+    #[account(mut)]
+    pub fee_source: Account<'info, TokenAccount>,
+    /// Account receiving the fees.
+    #[account(mut)]
+    pub fee_recipient: Account<'info, TokenAccount>,
+    /// [Token] program.
+    pub token_program: Program<'info, Token>,
+}
+
+/// Accounts for [asol::set_referral_fee].
+#[derive(Accounts)]
+pub struct SetReferralFee<'info> {
+    /// [Aggregate].
+    #[account(mut)]
+    pub aggregate: Account<'info, Aggregate>,
+    /// The [Aggregate::curator].
+    pub curator: Signer<'info>,
+}
+
+/// Accounts for [asol::set_performance_fee].
+#[derive(Accounts)]
+pub struct SetPerformanceFee<'info> {
+    /// [Aggregate].
+    #[account(mut)]
+    pub aggregate: Account<'info, Aggregate>,
+    /// The [Aggregate::curator].
+    pub curator: Signer<'info>,
+    /// The [crate_token::CrateToken].
+    pub crate_token: Account<'info, crate_token::CrateToken>,
+    /// The [Aggregate::treasury] to set.
+    pub treasury: Account<'info, TokenAccount>,
+}
+
+/// Accounts for [asol::claim_performance_fee].
+#[derive(Accounts)]
+pub struct ClaimPerformanceFee<'info> {
+    /// [Aggregate].
+    #[account(mut)]
+    pub aggregate: Account<'info, Aggregate>,
+
+    /// Information about the crate. Its issue fee is waived for the claim.
+    #[account(mut)]
+    pub crate_token: Box<Account<'info, crate_token::CrateToken>>,
+
+    /// [Mint] of the [crate_token::CrateToken].
+    #[account(mut)]
+    pub crate_mint: Box<Account<'info, Mint>>,
+
+    /// The [Aggregate::treasury].
+    #[account(mut)]
+    pub treasury: Box<Account<'info, TokenAccount>>,
+
+    /// Destination of the author share of the issue fee, the [Aggregate::author_fee_destination].
+    /// No issue fee is charged on the claim, but the [crate_token::CrateToken] requires it.
+    #[account(mut)]
+    pub author_fee_destination: Box<Account<'info, TokenAccount>>,
+
+    /// Destination of the protocol share of the issue fee, the [Aggregate::protocol_fee_destination].
+    /// No issue fee is charged on the claim, but the [crate_token::CrateToken] requires it.
+    #[account(mut)]
+    pub protocol_fee_destination: Box<Account<'info, TokenAccount>>,
+
+    /// [Token] program.
+    pub token_program: Program<'info, Token>,
+
+    /// [crate_token::program::CrateToken] program.
+    pub crate_token_program: Program<'info, crate_token::program::CrateToken>,
+}
+
+/// Accounts for [asol::set_single_withdraw_fee].
+#[derive(Accounts)]
+pub struct SetSingleWithdrawFee<'info> {
+    /// [Aggregate].
+    #[account(mut)]
+    pub aggregate: Account<'info, Aggregate>,
+    /// The [Aggregate::curator].
+    pub curator: Signer<'info>,
+}
+
+/// Accounts for minting aSOL.
+#[derive(Accounts)]
+pub struct MintASol<'info> {
+    /// Information about the aggregate.
+    #[account(mut)]
+    pub aggregate: Account<'info, Aggregate>,
+
+    /// The [SnapshotHistory] of the aggregate.
+    #[account(mut)]
+    pub snapshot_history: Account<'info, SnapshotHistory>,
+
+    /// The [StakePool].
+    #[account(mut)]
+    pub stake_pool: Account<'info, StakePool>,
+
+    /// [TokenAccount] holding the [StakePool] tokens of the [crate_token::CrateToken].
+    #[account(mut)]
+    pub stake_pool_tokens: Box<Account<'info, TokenAccount>>,
+
+    /// Information about the crate.
+    pub crate_token: Box<Account<'info, crate_token::CrateToken>>,
+
+    /// [Mint] of the [crate_token::CrateToken].
+    #[account(mut)]
+    pub crate_mint: Box<Account<'info, Mint>>,
+
+    /// The depositor into the pool.
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+
+    /// The source of the deposited [StakePool] tokens.
+    #[account(mut)]
+    pub depositor_source: Box<Account<'info, TokenAccount>>,
+
+    /// Destination of the issued tokens.
+    #[account(mut)]
+    pub mint_destination: Box<Account<'info, TokenAccount>>,
+
+    /// Destination of the author share of the issue fee, the [Aggregate::author_fee_destination].
+    #[account(mut)]
+    pub author_fee_destination: Box<Account<'info, TokenAccount>>,
+
+    /// Destination of the protocol share of the issue fee, the [Aggregate::protocol_fee_destination].
+    #[account(mut)]
+    pub protocol_fee_destination: Box<Account<'info, TokenAccount>>,
+
+    /// [Token] program.
+    pub token_program: Program<'info, Token>,
+
+    /// [crate_token::program::CrateToken] program.
+    pub crate_token_program: Program<'info, crate_token::program::CrateToken>,
+}
+
+/// Accounts for [asol::new_exchange_rate_oracle].
+#[derive(Accounts)]
+#[instruction(bump: u8)]
+pub struct NewExchangeRateOracle<'info> {
+    pub aggregate: Account<'info, Aggregate>,
+
+    /// The [ExchangeRateOracle] to create.
+    #[account(
+        init,
+        seeds = [
+            b"ExchangeRateOracle",
+            aggregate.key().to_bytes().as_ref()
+        ],
+        bump = bump,
+        payer = payer,
+        space = 8 + std::mem::size_of::<ExchangeRateOracle>()
+            + (std::mem::size_of::<StakePoolSnapshot>() + std::mem::size_of::<PoolExchangeRate>()) * MAX_STAKE_POOLS
+    )]
+    pub exchange_rate_oracle: Account<'info, ExchangeRateOracle>,
+
+    /// Payer of the oracle initialization.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// System program.
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts for [asol::get_exchange_rate].
+#[derive(Accounts)]
+pub struct GetExchangeRate<'info> {
+    /// Information about the aggregate.
+    pub aggregate: Account<'info, Aggregate>,
+
+    /// The [ExchangeRateOracle] to write to.
+    #[account(mut)]
+    pub exchange_rate_oracle: Account<'info, ExchangeRateOracle>,
+
+    /// Information about the crate.
+    pub crate_token: Box<Account<'info, crate_token::CrateToken>>,
+
+    /// [Mint] of the [crate_token::CrateToken].
+    pub crate_mint: Box<Account<'info, Mint>>,
+}
+
+/// Accounts for [asol::refresh].
+#[derive(Accounts)]
+pub struct Refresh<'info> {
+    /// Information about the aggregate.
+    #[account(mut)]
+    pub aggregate: Account<'info, Aggregate>,
+
+    /// The [SnapshotHistory] of the aggregate.
+    #[account(mut)]
+    pub snapshot_history: Account<'info, SnapshotHistory>,
+
+    /// Information about the crate.
+    pub crate_token: Box<Account<'info, crate_token::CrateToken>>,
+
+    /// [Mint] of the [crate_token::CrateToken].
+    pub crate_mint: Box<Account<'info, Mint>>,
+}
+
+/// A deposit of stake pool tokens in [asol::mint_multi].
+#[derive(AnchorSerialize, AnchorDeserialize, Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct MintDeposit {
+    /// Mint of the stake pool tokens.
+    pub mint: Pubkey,
+    /// Amount of stake pool tokens to deposit.
+    pub amount: u64,
+}
+
+/// The [MintOptionalAccounts] passed to a mint.
+#[derive(AnchorSerialize, AnchorDeserialize, Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct MintOptions {
+    /// If true, the [MintReferrer] accounts are passed.
+    pub referrer: bool,
+    /// If true, the depositor's [Position] is passed, after the [MintReferrer] accounts.
+    pub position: bool,
+}
+
+/// Accounts for [asol::mint_multi].
+#[derive(Accounts)]
+pub struct MintMulti<'info> {
+    /// Information about the aggregate.
+    #[account(mut)]
+    pub aggregate: Account<'info, Aggregate>,
+
+    /// The [SnapshotHistory] of the aggregate.
+    #[account(mut)]
+    pub snapshot_history: Account<'info, SnapshotHistory>,
+
+    /// Information about the crate.
+    pub crate_token: Box<Account<'info, crate_token::CrateToken>>,
+
+    /// [Mint] of the [crate_token::CrateToken].
+    #[account(mut)]
+    pub crate_mint: Box<Account<'info, Mint>>,
+
+    /// The depositor into the pools.
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+
+    /// Destination of the issued tokens.
+    #[account(mut)]
+    pub mint_destination: Box<Account<'info, TokenAccount>>,
+
+    /// Destination of the author share of the issue fee, the [Aggregate::author_fee_destination].
+    #[account(mut)]
+    pub author_fee_destination: Box<Account<'info, TokenAccount>>,
+
+    /// Destination of the protocol share of the issue fee, the [Aggregate::protocol_fee_destination].
+    #[account(mut)]
+    pub protocol_fee_destination: Box<Account<'info, TokenAccount>>,
+
+    /// [Token] program.
+    pub token_program: Program<'info, Token>,
+
+    /// [crate_token::program::CrateToken] program.
+    pub crate_token_program: Program<'info, crate_token::program::CrateToken>,
+}
+
+/// Accounts for a single deposit in [asol::mint_multi], passed via remaining accounts.
+#[derive(Accounts)]
+pub struct MintMultiPool<'info> {
+    /// The [StakePool].
+    #[account(mut)]
+    pub stake_pool: Account<'info, StakePool>,
+
+    /// [TokenAccount] holding the [StakePool] tokens of the [crate_token::CrateToken].
+    #[account(mut)]
+    pub stake_pool_tokens: Box<Account<'info, TokenAccount>>,
+
+    /// The source of the deposited [StakePool] tokens.
+    #[account(mut)]
+    pub depositor_source: Box<Account<'info, TokenAccount>>,
+}
+
+/// Accounts of the [Referrer] of a mint, passed via remaining accounts.
+#[derive(Accounts)]
+pub struct MintReferrer<'info> {
+    /// The [Referrer].
+    #[account(mut)]
+    pub referrer: Account<'info, Referrer>,
+
+    /// The [Referrer::fee_destination].
+    #[account(mut)]
+    pub referrer_fee_destination: Box<Account<'info, TokenAccount>>,
+}
+
+/// Optional accounts of a mint, passed after all other remaining accounts as specified by the [MintOptions].
+pub struct MintOptionalAccounts<'info> {
+    /// The [Referrer] of the deposit.
+    pub referrer: Option<MintReferrer<'info>>,
+    /// The depositor's [Position].
+    pub position: Option<Account<'info, Position>>,
+}
+
+#[derive(Accounts)]
+pub struct SyncAndMint<'info> {
+    /// Mint aSOL
+    pub mint_asol: MintASol<'info>,
+}
+
+/// Accounts for [asol::deposit_sol].
+#[derive(Accounts)]
+pub struct DepositSol<'info> {
+    /// Mint aSOL. The [MintASol::depositor_source] receives the stake pool tokens of the deposit.
+    pub mint_asol: MintASol<'info>,
+
+    /// System program.
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts for depositing SOL into Marinade in [asol::deposit_sol], passed via remaining accounts.
+#[derive(Accounts)]
+pub struct DepositSolMarinade<'info> {
+    /// [marinade] program.
+    pub marinade_program: Program<'info, marinade::program::Marinade>,
+    /// [marinade::State::msol_mint].
+    #[account(mut)]
+    pub msol_mint: UncheckedAccount<'info>,
+    /// SOL leg of the Marinade liquidity pool.
+    #[account(mut)]
+    pub liq_pool_sol_leg_pda: UncheckedAccount<'info>,
+    /// mSOL leg of the Marinade liquidity pool.
+    #[account(mut)]
+    pub liq_pool_msol_leg: UncheckedAccount<'info>,
+    /// Authority of the mSOL leg of the Marinade liquidity pool.
+    pub liq_pool_msol_leg_authority: UncheckedAccount<'info>,
+    /// Marinade SOL reserve.
+    #[account(mut)]
+    pub reserve_pda: UncheckedAccount<'info>,
+    /// Mint authority of the mSOL mint.
+    pub msol_mint_authority: UncheckedAccount<'info>,
+}
+
+/// Accounts for depositing SOL into Lido in [asol::deposit_sol], passed via remaining accounts.
+#[derive(Accounts)]
+pub struct DepositSolLido<'info> {
+    /// [lido_anchor] program.
+    pub lido_program: UncheckedAccount<'info>,
+    /// The stSOL mint.
+    #[account(mut)]
+    pub st_sol_mint: UncheckedAccount<'info>,
+    /// Lido SOL reserve.
+    #[account(mut)]
+    pub reserve_account: UncheckedAccount<'info>,
+    /// Mint authority of the stSOL mint.
+    pub mint_authority: UncheckedAccount<'info>,
+}
+
+/// Accounts for [asol::deposit_stake_account].
+#[derive(Accounts)]
+pub struct DepositStakeAccount<'info> {
+    /// Mint aSOL. The [MintASol::depositor_source] receives the stake pool tokens of the deposit.
+    pub mint_asol: MintASol<'info>,
+
+    /// The stake account to deposit. Its staker and withdrawer must be the [MintASol::depositor].
+    #[account(mut)]
+    pub stake_account: UncheckedAccount<'info>,
+
+    /// Clock sysvar.
+    pub clock: Sysvar<'info, Clock>,
+    /// Rent sysvar.
+    pub rent: Sysvar<'info, Rent>,
+    /// Stake history sysvar.
+    pub stake_history: UncheckedAccount<'info>,
+
+    /// System program.
+    pub system_program: Program<'info, System>,
+    /// Stake program.
+    pub stake_program: UncheckedAccount<'info>,
+}
+
+/// Accounts for depositing a stake account into Marinade in [asol::deposit_stake_account],
+/// passed via remaining accounts.
+#[derive(Accounts)]
+pub struct DepositStakeMarinade<'info> {
+    /// [marinade] program.
+    pub marinade_program: Program<'info, marinade::program::Marinade>,
+    /// Marinade validator list.
+    #[account(mut)]
+    pub validator_list: UncheckedAccount<'info>,
+    /// Marinade stake list.
+    #[account(mut)]
+    pub stake_list: UncheckedAccount<'info>,
+    /// Flag preventing the same stake account from being deposited twice.
+    #[account(mut)]
+    pub duplication_flag: UncheckedAccount<'info>,
+    /// [marinade::State::msol_mint].
+    #[account(mut)]
+    pub msol_mint: UncheckedAccount<'info>,
+    /// Mint authority of the mSOL mint.
+    pub msol_mint_authority: UncheckedAccount<'info>,
+}
+
+/// Accounts for depositing a stake account into an SPL stake pool in [asol::deposit_stake_account],
+/// passed via remaining accounts.
+#[derive(Accounts)]
+pub struct DepositStakeSplStakePool<'info> {
+    /// SPL stake pool program.
+    pub spl_stake_pool_program: UncheckedAccount<'info>,
+    /// [spl_stake_pool_anchor::StakePool::validator_list].
+    #[account(mut)]
+    pub validator_list: UncheckedAccount<'info>,
+    /// Stake deposit authority of the pool.
+    pub deposit_authority: UncheckedAccount<'info>,
+    /// Stake withdraw authority of the pool.
+    pub withdraw_authority: UncheckedAccount<'info>,
+    /// Validator stake account the deposited stake is merged into.
+    #[account(mut)]
+    pub validator_stake: UncheckedAccount<'info>,
+    /// [spl_stake_pool_anchor::StakePool::reserve_stake].
+    #[account(mut)]
+    pub reserve_stake: UncheckedAccount<'info>,
+    /// [spl_stake_pool_anchor::StakePool::manager_fee_account].
+    #[account(mut)]
+    pub manager_fee_account: UncheckedAccount<'info>,
+    /// [spl_stake_pool_anchor::StakePool::pool_mint].
+    #[account(mut)]
+    pub pool_mint: UncheckedAccount<'info>,
+}
+
+/// Accounts for redeeming aSOL.
+#[derive(Accounts)]
+pub struct RedeemASol<'info> {
+    /// Information about the aggregate.
+    #[account(mut)]
+    pub aggregate: Account<'info, Aggregate>,
+
+    /// The [SnapshotHistory] of the aggregate.
+    #[account(mut)]
+    pub snapshot_history: Account<'info, SnapshotHistory>,
+
+    /// Information about the crate.
+    pub crate_token: Box<Account<'info, crate_token::CrateToken>>,
+
+    /// [Mint] of the [crate_token::CrateToken].
+    #[account(mut)]
+    pub crate_mint: Box<Account<'info, Mint>>,
+
+    /// The redeemer of the aSOL.
+    pub redeemer: Signer<'info>,
+
+    /// The source of the aSOL to burn.
+    #[account(mut)]
+    pub redeemer_source: Box<Account<'info, TokenAccount>>,
+
+    /// [Token] program.
+    pub token_program: Program<'info, Token>,
+
+    /// [crate_token::program::CrateToken] program.
+    pub crate_token_program: Program<'info, crate_token::program::CrateToken>,
+}
+
+/// Accounts for a single stake pool in [asol::redeem_asol], passed via remaining accounts.
+#[derive(Accounts)]
+pub struct RedeemASolPool<'info> {
+    /// The [StakePool].
+    #[account(mut)]
+    pub stake_pool: Account<'info, StakePool>,
+
+    /// [TokenAccount] holding the [StakePool] tokens of the [crate_token::CrateToken].
+    #[account(mut)]
+    pub crate_underlying: Box<Account<'info, TokenAccount>>,
+
+    /// Destination of the withdrawn [StakePool] tokens.
+    #[account(mut)]
+    pub withdraw_destination: Box<Account<'info, TokenAccount>>,
+
+    /// Destination of the author fee tokens.
+    #[account(mut)]
+    pub author_fee_destination: Box<Account<'info, TokenAccount>>,
+
+    /// Destination of the protocol fee tokens.
+    #[account(mut)]
+    pub protocol_fee_destination: Box<Account<'info, TokenAccount>>,
+}
+
+#[derive(Accounts)]
+pub struct SyncAndRedeem<'info> {
+    /// Redeem aSOL
+    pub redeem_asol: RedeemASol<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SyncAndWithdrawSingle<'info> {
+    /// Redeem aSOL
+    pub redeem_asol: RedeemASol<'info>,
+    /// The stake pool to withdraw from
+    pub pool: RedeemASolPool<'info>,
+}
+
+/// Accounts for synchronizing every stake pool in an [Aggregate].
+///
+/// These are loaded from remaining accounts as one (pool state, reserve [TokenAccount]) pair
+/// per [Aggregate::stake_pools] entry, in the same order.
+pub struct SyncAll<'info> {
+    /// Accounts of each stake pool, in the order of [Aggregate::stake_pools].
+    pub stake_pools: Vec<SyncStakePool<'info>>,
+}
+
+/// Accounts for synchronizing a stake pool, based on its [AccountingMethod].
+pub enum SyncStakePool<'info> {
+    /// Marinade accounts.
+    Marinade(SyncMarinade<'info>),
+    /// Lido accounts.
+    Lido(SyncLido<'info>),
+    /// SPL stake pool accounts.
+    SplStakePool(SyncSplStakePool<'info>),
+}
+
+#[derive(Accounts)]
+pub struct SyncMarinade<'info> {
+    /// [marinade] state account.
+    pub marinade: Box<Account<'info, marinade::State>>,
+
+    /// [TokenAccount] holding the tokens of the [StakePool].
+    pub marinade_stake_pool_tokens: Box<Account<'info, TokenAccount>>,
+}
+
+#[derive(Accounts)]
 pub struct SyncLido<'info> {
     /// [lido_anchor] account.
     pub lido: Box<Account<'info, lido_anchor::Lido>>,
@@ -357,6 +1877,16 @@ pub struct SyncLido<'info> {
     pub lido_stake_pool_tokens: Box<Account<'info, TokenAccount>>,
 }
 
+/// Accounts for synchronizing an SPL stake pool.
+#[derive(Accounts)]
+pub struct SyncSplStakePool<'info> {
+    /// [spl_stake_pool_anchor] stake pool account, owned by the [StakePoolMeta::pool_program].
+    pub spl_stake_pool: UncheckedAccount<'info>,
+
+    /// [TokenAccount] holding the tokens of the [StakePool].
+    pub spl_stake_pool_tokens: Box<Account<'info, TokenAccount>>,
+}
+
 /// Errors.
 #[error]
 pub enum ErrorCode {
@@ -364,9 +1894,77 @@ pub enum ErrorCode {
     UnauthorizedNotCurator,
     #[msg("Must be curator setter.")]
     UnauthorizedNotCuratorSetter,
+    #[msg("Must be pending curator.")]
+    UnauthorizedNotPendingCurator,
+    #[msg("Must be pending curator setter.")]
+    UnauthorizedNotPendingCuratorSetter,
+    #[msg("Must be guardian.")]
+    UnauthorizedNotGuardian,
 
     #[msg("Pool not found in snapshot.", offset = 10)]
     PoolNotFoundInSnapshot,
     #[msg("Cannot add a pool that has already been added.")]
     PoolAlreadyAdded,
+    #[msg("Must provide accounts for every stake pool in the aggregate.")]
+    PoolAccountsMismatch,
+    #[msg("Stake pool does not use the accounting method of the instruction.")]
+    AccountingMethodMismatch,
+    #[msg("Insufficient stake pool balance for withdrawal.")]
+    InsufficientPoolBalance,
+    #[msg("Single withdraw fee is too high.")]
+    SingleWithdrawFeeTooHigh,
+    #[msg("Stake pool is retired.")]
+    StakePoolRetired,
+    #[msg("Crate reserves of the stake pool must be empty.")]
+    StakePoolReservesNotEmpty,
+    #[msg("Max weight cannot exceed 100%.")]
+    MaxWeightTooHigh,
+    #[msg("Deposit would exceed the deposit cap of the stake pool.")]
+    DepositCapExceeded,
+    #[msg("Deposit would exceed the max weight of the stake pool.")]
+    MaxWeightExceeded,
+    #[msg("Deposit amount must be non-zero.")]
+    ZeroDeposit,
+    #[msg("Deposit is too small to mint any aSOL.")]
+    ZeroMint,
+    #[msg("Mint amount is below the minimum.")]
+    MintAmountBelowMinimum,
+    #[msg("No stake pool accepts SOL deposits.")]
+    NoSolDepositPool,
+    #[msg("SOL must be deposited into the stake pool furthest below its max weight.")]
+    NotLeastWeightedPool,
+    #[msg("Stake pool exchange rate is stale.")]
+    StaleExchangeRate,
+    #[msg("Marinade is in emergency mode.")]
+    MarinadeEmergencyMode,
+    #[msg("Min price cannot exceed max price.")]
+    InvalidPriceBand,
+    #[msg("Stake pool price is outside of the price band.")]
+    PriceOutOfBand,
+    #[msg("Invalid pause flags.")]
+    InvalidPauseFlags,
+    #[msg("Aggregate is paused.")]
+    AggregatePaused,
+    #[msg("Stake pool is paused.")]
+    StakePoolPaused,
+    #[msg("Issue fee is too high.")]
+    IssueFeeTooHigh,
+    #[msg("Withdraw fee is too high.")]
+    WithdrawFeeTooHigh,
+    #[msg("Fee destinations must be set before charging an issue fee.")]
+    FeeDestinationsNotSet,
+    #[msg("Performance fee is too high.")]
+    PerformanceFeeTooHigh,
+    #[msg("Referral fee is too high.")]
+    ReferralFeeTooHigh,
+    #[msg("Depositor cannot be its own referrer.")]
+    SelfReferral,
+    #[msg("Stake pool appears more than once.")]
+    DuplicateStakePool,
+    #[msg("There are no fees to sweep.")]
+    ZeroSweep,
+    #[msg("Redeem amount must be non-zero.")]
+    ZeroRedeem,
+    #[msg("Crate withdraw authority is not the aggregate. Redeem in kind instead.")]
+    WithdrawAuthorityNotAggregate,
 }
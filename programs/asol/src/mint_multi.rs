@@ -0,0 +1,235 @@
+use crate::{
+    mul_div, AccountingMethod, MintDeposit, MintMulti, MintMultiPool, MintOptionalAccounts,
+    MintOptions, Rounding, SyncAll, ASOL, SOL,
+};
+use anchor_lang::{prelude::*, solana_program::account_info::next_account_infos};
+use vipers::{assert_keys, unwrap_int, unwrap_or_err};
+
+/// Number of accounts in a [MintMultiPool].
+const MINT_MULTI_POOL_ACCOUNTS: usize = 3;
+
+/// A deposit of [crate::asol::mint_multi], priced against the snapshot.
+struct PricedDeposit<'info> {
+    /// Accounts of the deposit.
+    pool: MintMultiPool<'info>,
+    /// Accounting method of the stake pool.
+    accounting_method: AccountingMethod,
+    /// Amount of stake pool tokens deposited.
+    deposit_amount: u64,
+    /// Amount of aSOL minted for the deposit, before the issue fee.
+    mint_amount: ASOL,
+}
+
+impl<'info> MintMulti<'info> {
+    /// Mints aSOL for deposits into several stake pools, all priced against the same snapshot.
+    pub fn mint_multi(
+        &mut self,
+        remaining_accounts: &[AccountInfo<'info>],
+        deposits: Vec<MintDeposit>,
+        min_mint_amount: u64,
+        options: MintOptions,
+    ) -> ProgramResult {
+        require!(!deposits.is_empty(), ZeroDeposit);
+
+        // sync accounts come first, followed by the accounts of each deposit and the optional accounts
+        let (sync, rest) = SyncAll::load(&self.aggregate, remaining_accounts)?;
+        let num_pool_accounts = unwrap_int!(deposits.len().checked_mul(MINT_MULTI_POOL_ACCOUNTS));
+        require!(rest.len() >= num_pool_accounts, PoolAccountsMismatch);
+        let (pool_accounts, optional_accounts) = rest.split_at(num_pool_accounts);
+        let mut optional = MintOptionalAccounts::load(
+            &self.aggregate,
+            self.depositor.key(),
+            options,
+            optional_accounts,
+        )?;
+        sync.check_exchange_rates()?;
+
+        let snapshot = sync.sync(&mut self.aggregate, &self.crate_mint)?;
+
+        // price every deposit against the same snapshot
+        let pool_accounts_iter = &mut pool_accounts.iter();
+        let mut priced: Vec<PricedDeposit> = vec![];
+        let mut total_sol_value = SOL::from(0);
+        let mut total_mint_amount = ASOL::from(0);
+        for deposit in deposits.iter() {
+            require!(deposit.amount > 0, ZeroDeposit);
+            require!(
+                priced
+                    .iter()
+                    .all(|other| other.pool.stake_pool.mint != deposit.mint),
+                DuplicateStakePool
+            );
+
+            let pool: MintMultiPool = Accounts::try_accounts(
+                &crate::ID,
+                &mut next_account_infos(pool_accounts_iter, MINT_MULTI_POOL_ACCOUNTS)?,
+                &[],
+            )?;
+            pool.validate_for_deposit(self, deposit.mint)?;
+
+            let minter = sync.find_stake_pool(deposit.mint)?;
+            assert_keys!(
+                *pool.stake_pool_tokens,
+                *minter.crate_reserves(),
+                "stake_pool_tokens should be the crate reserves"
+            );
+            let pool_snapshot = unwrap_or_err!(
+                snapshot
+                    .stake_pools
+                    .iter()
+                    .find(|pool| pool.pool_mint == deposit.mint),
+                PoolNotFoundInSnapshot
+            );
+            pool_snapshot.check_price_band(&pool.stake_pool)?;
+            let deposit_sol_value = minter.sol_value(deposit.amount)?;
+            snapshot.check_deposit_limits(&pool.stake_pool, pool_snapshot, deposit_sol_value)?;
+            let mint_amount = snapshot.compute_asol_amount_from_sol(deposit_sol_value)?;
+            require!(mint_amount.amount > 0, ZeroMint);
+
+            total_sol_value = unwrap_int!(total_sol_value.checked_add(deposit_sol_value));
+            total_mint_amount = unwrap_int!(total_mint_amount.checked_add(mint_amount));
+            priced.push(PricedDeposit {
+                pool,
+                accounting_method: minter.accounting_method(),
+                deposit_amount: deposit.amount,
+                mint_amount,
+            });
+        }
+
+        // the depositor receives the minted amount net of the issue fee
+        let issue_fees = self.crate_token.apply_issue_fee(total_mint_amount.amount)?;
+        let received_amount = issue_fees.amount;
+        require!(received_amount >= min_mint_amount, MintAmountBelowMinimum);
+
+        // transfer stake pool tokens to the crate
+        for deposit in priced.iter() {
+            anchor_spl::token::transfer(
+                CpiContext::new(
+                    self.token_program.to_account_info(),
+                    anchor_spl::token::Transfer {
+                        from: deposit.pool.depositor_source.to_account_info(),
+                        to: deposit.pool.stake_pool_tokens.to_account_info(),
+                        authority: self.depositor.to_account_info(),
+                    },
+                ),
+                deposit.deposit_amount,
+            )?;
+        }
+
+        // issue new crate tokens once for all deposits
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"Aggregate".as_ref(),
+            &self.aggregate.crate_token.to_bytes(),
+            &[self.aggregate.bump],
+        ]];
+        crate_token::cpi::issue(
+            CpiContext::new_with_signer(
+                self.crate_token_program.to_account_info(),
+                crate_token::cpi::accounts::Issue {
+                    crate_token: self.crate_token.to_account_info(),
+                    crate_mint: self.crate_mint.to_account_info(),
+                    issue_authority: self.aggregate.to_account_info(),
+                    mint_destination: self.mint_destination.to_account_info(),
+                    author_fee_destination: self.author_fee_destination.to_account_info(),
+                    protocol_fee_destination: self.protocol_fee_destination.to_account_info(),
+
+                    token_program: self.token_program.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            total_mint_amount.amount,
+        )?;
+
+        // pay the referral fee out of the author share of the issue fee
+        let referral_fee = match &mut optional.referrer {
+            Some(referrer) => referrer.pay_referral_fee(
+                &self.aggregate,
+                self.author_fee_destination.to_account_info(),
+                &self.token_program,
+                issue_fees.author_fee,
+                total_sol_value,
+                total_mint_amount,
+            )?,
+            None => 0,
+        };
+        let referrer = optional
+            .referrer
+            .as_ref()
+            .map(|referrer| referrer.referrer.owner);
+
+        let now = Clock::get()?.unix_timestamp;
+        let num_deposits = priced.len();
+        let mut unallocated_referral_fee = referral_fee;
+        for (i, deposit) in priced.iter_mut().enumerate() {
+            // split the referral fee pro rata by mint amount, the last deposit receiving the rounding remainder
+            let deposit_referral_fee = if i + 1 == num_deposits {
+                unallocated_referral_fee
+            } else {
+                unwrap_int!(mul_div(
+                    referral_fee,
+                    deposit.mint_amount.amount,
+                    total_mint_amount.amount,
+                    Rounding::Down
+                ))
+            };
+            unallocated_referral_fee =
+                unwrap_int!(unallocated_referral_fee.checked_sub(deposit_referral_fee));
+
+            let stake_pool_state = &mut deposit.pool.stake_pool;
+
+            // track the deposited reserves
+            unwrap_or_err!(
+                self.aggregate.find_stake_pool_mut(stake_pool_state.mint),
+                PoolNotFoundInSnapshot
+            )
+            .record_deposit(deposit.deposit_amount)?;
+
+            // update stats and record snapshot
+            stake_pool_state.record_mint(deposit.deposit_amount, deposit.mint_amount)?;
+            let pool_snapshot = unwrap_or_err!(
+                snapshot
+                    .stake_pools
+                    .iter()
+                    .find(|pool| pool.pool_mint == stake_pool_state.mint),
+                PoolNotFoundInSnapshot
+            );
+            stake_pool_state.record_snapshot(&snapshot, pool_snapshot, now);
+            stake_pool_state.exit(&crate::ID)?;
+
+            emit!(crate::MintASolEvent {
+                depositor: self.depositor.key(),
+                stake_pool_mint: stake_pool_state.mint,
+                accounting_method: deposit.accounting_method,
+                deposit_amount: deposit.deposit_amount,
+                mint_amount: deposit.mint_amount.amount,
+                referrer,
+                referral_fee: deposit_referral_fee,
+                timestamp: now
+            });
+        }
+
+        // record aggregate snapshot
+        let aggregate = &mut self.aggregate;
+        aggregate.latest_snapshot = snapshot.clone();
+        aggregate.latest_snapshot_ts = now;
+        self.snapshot_history.record(&snapshot)?;
+
+        // update the depositor's position
+        if let Some(mut position) = optional.position {
+            position.record_deposit(total_sol_value, ASOL::from(received_amount), now)?;
+            position.exit(&crate::ID)?;
+        }
+
+        emit!(crate::MintMultiEvent {
+            depositor: self.depositor.key(),
+            stake_pool_mints: deposits.iter().map(|deposit| deposit.mint).collect(),
+            deposit_sol_value: total_sol_value.amount,
+            mint_amount: total_mint_amount.amount,
+            referrer,
+            referral_fee,
+            timestamp: now
+        });
+
+        Ok(())
+    }
+}
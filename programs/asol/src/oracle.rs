@@ -0,0 +1,60 @@
+use crate::{mul_div, GetExchangeRate, PoolExchangeRate, Rounding, SyncAll, MAX_BPS, SOL};
+use anchor_lang::prelude::*;
+use num_traits::ToPrimitive;
+use vipers::unwrap_int;
+
+impl<'info> GetExchangeRate<'info> {
+    /// Computes the current exchange rate and writes it to the [crate::ExchangeRateOracle].
+    pub fn get_exchange_rate(
+        &mut self,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> ProgramResult {
+        let (sync, rest) = SyncAll::load(&self.aggregate, remaining_accounts)?;
+        require!(rest.is_empty(), PoolAccountsMismatch);
+        let snapshot = sync.preview(&self.aggregate, &self.crate_mint)?;
+
+        let clock = Clock::get()?;
+        let mut stake_pools: Vec<PoolExchangeRate> = vec![];
+        for (pool_sync, pool_snapshot) in sync.stake_pools.iter().zip(snapshot.stake_pools.iter()) {
+            let balance_sol = unwrap_int!(pool_snapshot.pool_balance_sol(Rounding::Down));
+            let weight_bps = if snapshot.balance_sol.amount == 0 {
+                0
+            } else {
+                unwrap_int!(mul_div(
+                    balance_sol,
+                    MAX_BPS.into(),
+                    snapshot.balance_sol.amount,
+                    Rounding::Down
+                )
+                .and_then(|weight| weight.to_u16()))
+            };
+            stake_pools.push(PoolExchangeRate {
+                pool_mint: pool_snapshot.pool_mint,
+                balance_sol: SOL::from(balance_sol),
+                weight_bps,
+                is_current: pool_sync.check_exchange_rate(&clock).is_ok(),
+            });
+        }
+
+        let oracle = &mut self.exchange_rate_oracle;
+        oracle.asol_price = snapshot.asol_price()?;
+        oracle.is_current = stake_pools.iter().all(|pool| pool.is_current);
+        oracle.stake_pools = stake_pools;
+        oracle.snapshot = snapshot;
+        oracle.timestamp = clock.unix_timestamp;
+        oracle.slot = clock.slot;
+        oracle.snapshot_age = unwrap_int!(clock
+            .unix_timestamp
+            .checked_sub(self.aggregate.latest_snapshot_ts));
+
+        emit!(crate::ExchangeRateEvent {
+            aggregate: oracle.aggregate,
+            asol_price: oracle.asol_price.amount,
+            snapshot_age: oracle.snapshot_age,
+            is_current: oracle.is_current,
+            timestamp: oracle.timestamp
+        });
+
+        Ok(())
+    }
+}
@@ -1,63 +1,258 @@
 use crate::{
-    accounting::Accountant, types::SOL, AccountingMethod, MintASol, Snapshot, StakePoolSnapshot,
-    SyncAndMint, ASOL,
+    accounting::Accountant, types::SOL, AccountingMethod, Aggregate, MintASol,
+    MintOptionalAccounts, MintOptions, Rounding, Snapshot, StakePoolMeta, StakePoolSnapshot,
+    SyncAll, SyncAndMint, SyncStakePool, ASOL,
 };
 use anchor_lang::prelude::*;
-use vipers::{unwrap_int, unwrap_or_err};
+use anchor_spl::token::{Mint, TokenAccount};
+use vipers::{assert_ata, assert_keys, unwrap_int, unwrap_or_err, validate::Validate};
+
+/// Number of accounts used to synchronize a single stake pool.
+const SYNC_STAKE_POOL_ACCOUNTS: usize = 2;
+
+impl<'info> SyncStakePool<'info> {
+    /// Loads and validates the accounts of a stake pool.
+    /// The reserves must be the associated token account of the [crate_token::CrateToken] for the pool mint.
+    fn load(
+        crate_token: Pubkey,
+        pool: &StakePoolMeta,
+        accounts: &[AccountInfo<'info>],
+    ) -> Result<Self, ProgramError> {
+        let accounts = &mut &accounts[..];
+        let sync = match pool.accounting_method {
+            AccountingMethod::Marinade => {
+                SyncStakePool::Marinade(Accounts::try_accounts(&crate::ID, accounts, &[])?)
+            }
+            AccountingMethod::Lido => {
+                SyncStakePool::Lido(Accounts::try_accounts(&crate::ID, accounts, &[])?)
+            }
+            AccountingMethod::SplStakePool => {
+                SyncStakePool::SplStakePool(Accounts::try_accounts(&crate::ID, accounts, &[])?)
+            }
+        };
+        sync.validate()?;
+        if let SyncStakePool::SplStakePool(sync) = &sync {
+            assert_keys!(
+                *sync.spl_stake_pool.owner,
+                pool.pool_program,
+                "spl_stake_pool.owner"
+            );
+        }
+        assert_ata!(
+            *sync.crate_reserves(),
+            crate_token,
+            pool.mint,
+            "crate_reserves"
+        );
+        Ok(sync)
+    }
+
+    /// The [AccountingMethod] of the stake pool.
+    pub fn accounting_method(&self) -> AccountingMethod {
+        match self {
+            SyncStakePool::Marinade(_) => AccountingMethod::Marinade,
+            SyncStakePool::Lido(_) => AccountingMethod::Lido,
+            SyncStakePool::SplStakePool(_) => AccountingMethod::SplStakePool,
+        }
+    }
+
+    /// Calculates the value of the stake pool token amount in SOL.
+    pub fn sol_value(&self, amount: u64) -> Result<SOL, ProgramError> {
+        match self {
+            SyncStakePool::Marinade(sync) => sync.sol_value(amount),
+            SyncStakePool::Lido(sync) => sync.sol_value(amount),
+            SyncStakePool::SplStakePool(sync) => sync.sol_value(amount),
+        }
+    }
+
+    /// Gets the [TokenAccount] of stake pool tokens associated with the Crate.
+    pub fn crate_reserves(&self) -> &Account<'info, TokenAccount> {
+        match self {
+            SyncStakePool::Marinade(sync) => &sync.marinade_stake_pool_tokens,
+            SyncStakePool::Lido(sync) => &sync.lido_stake_pool_tokens,
+            SyncStakePool::SplStakePool(sync) => &sync.spl_stake_pool_tokens,
+        }
+    }
+
+    /// Checks that the exchange rate of the stake pool is up to date.
+    pub fn check_exchange_rate(&self, clock: &Clock) -> ProgramResult {
+        match self {
+            SyncStakePool::Marinade(sync) => sync.check_exchange_rate(clock),
+            SyncStakePool::Lido(sync) => sync.check_exchange_rate(clock),
+            SyncStakePool::SplStakePool(sync) => sync.check_exchange_rate(clock),
+        }
+    }
+
+    /// Creates a snapshot of the stake pool.
+    pub fn snapshot(&self, pool: &StakePoolMeta) -> Result<StakePoolSnapshot, ProgramError> {
+        match self {
+            SyncStakePool::Marinade(sync) => StakePoolSnapshot::try_from_accountant(pool, sync),
+            SyncStakePool::Lido(sync) => StakePoolSnapshot::try_from_accountant(pool, sync),
+            SyncStakePool::SplStakePool(sync) => StakePoolSnapshot::try_from_accountant(pool, sync),
+        }
+    }
+}
+
+impl<'info> SyncAll<'info> {
+    /// Loads the accounts of every stake pool in the [Aggregate] from the start of the remaining accounts.
+    /// Returns the remaining accounts that were not used.
+    pub fn load<'a>(
+        aggregate: &Aggregate,
+        remaining_accounts: &'a [AccountInfo<'info>],
+    ) -> Result<(Self, &'a [AccountInfo<'info>]), ProgramError> {
+        let num_sync_accounts = unwrap_int!(aggregate
+            .stake_pools
+            .len()
+            .checked_mul(SYNC_STAKE_POOL_ACCOUNTS));
+        require!(
+            remaining_accounts.len() >= num_sync_accounts,
+            PoolAccountsMismatch
+        );
+        let (sync_accounts, rest) = remaining_accounts.split_at(num_sync_accounts);
+
+        let stake_pools = aggregate
+            .stake_pools
+            .iter()
+            .zip(sync_accounts.chunks_exact(SYNC_STAKE_POOL_ACCOUNTS))
+            .map(|(pool, accounts)| SyncStakePool::load(aggregate.crate_token, pool, accounts))
+            .collect::<Result<Vec<SyncStakePool<'info>>, ProgramError>>()?;
+
+        Ok((SyncAll { stake_pools }, rest))
+    }
+
+    /// Checks that the exchange rates of all stake pools are up to date.
+    /// This must be called before pricing deposits or withdrawals with a snapshot.
+    pub fn check_exchange_rates(&self) -> ProgramResult {
+        let clock = Clock::get()?;
+        for pool in self.stake_pools.iter() {
+            pool.check_exchange_rate(&clock)?;
+        }
+        Ok(())
+    }
+
+    /// Finds the accounts of the stake pool with the given mint.
+    pub fn find_stake_pool(&self, mint: Pubkey) -> Result<&SyncStakePool<'info>, ProgramError> {
+        Ok(unwrap_or_err!(
+            self.stake_pools
+                .iter()
+                .find(|pool| pool.crate_reserves().mint == mint),
+            PoolNotFoundInSnapshot
+        ))
+    }
 
-impl<'info> SyncAndMint<'info> {
     /// Builds a snapshot of all balances and conversions.
-    pub fn build_snapshot(&self) -> Result<Snapshot, ProgramError> {
-        let pool_snapshots: Vec<StakePoolSnapshot> = self
-            .mint_asol
-            .aggregate
+    pub fn build_snapshot(
+        &self,
+        aggregate: &Aggregate,
+        crate_mint: &Mint,
+    ) -> Result<Snapshot, ProgramError> {
+        let pool_snapshots: Vec<StakePoolSnapshot> = aggregate
             .stake_pools
             .iter()
-            .map(|pool| match pool.accounting_method {
-                AccountingMethod::Lido => {
-                    StakePoolSnapshot::try_from_accountant(pool, &self.sync.lido)
-                }
-                AccountingMethod::Marinade => {
-                    StakePoolSnapshot::try_from_accountant(pool, &self.sync.marinade)
-                }
-            })
+            .zip(self.stake_pools.iter())
+            .map(|(pool, sync)| sync.snapshot(pool))
             .collect::<Result<Vec<StakePoolSnapshot>, ProgramError>>()?;
 
         let balance_sol_u64: u64 = pool_snapshots
             .iter()
-            .map(|snap| Ok(unwrap_int!(snap.pool_balance_sol())))
+            .map(|snap| Ok(unwrap_int!(snap.pool_balance_sol(Rounding::Down))))
             .sum::<Result<u64, ProgramError>>()?;
         let balance_sol = SOL::from(balance_sol_u64);
 
         Ok(Snapshot {
             balance_sol,
-            supply: ASOL::from(self.mint_asol.crate_mint.supply),
+            supply: unwrap_int!(
+                ASOL::from(crate_mint.supply).checked_add(aggregate.pending_performance_fee)
+            ),
             stake_pools: pool_snapshots,
         })
     }
 
-    pub fn sync_and_mint_lido(&mut self, deposit_amount: u64) -> ProgramResult {
-        let snapshot = self.build_snapshot()?;
-        self.mint_asol
-            .mint_asol(&snapshot, &self.sync.lido, deposit_amount)?;
-        Ok(())
+    /// Builds a snapshot as [SyncAll::sync] would, without accruing the performance fee.
+    /// The fee that would accrue is included in the supply of the returned snapshot.
+    pub fn preview(
+        &self,
+        aggregate: &Aggregate,
+        crate_mint: &Mint,
+    ) -> Result<Snapshot, ProgramError> {
+        let mut snapshot = self.build_snapshot(aggregate, crate_mint)?;
+        let fee = snapshot
+            .compute_performance_fee(aggregate.high_water_mark, aggregate.performance_fee_bps)?;
+        snapshot.supply = unwrap_int!(snapshot.supply.checked_add(fee.fee_asol));
+        Ok(snapshot)
     }
 
-    pub fn sync_and_mint_marinade(&mut self, deposit_amount: u64) -> ProgramResult {
-        let snapshot = self.build_snapshot()?;
+    /// Builds a snapshot and accrues the performance fee on the yield earned since the last sync.
+    /// The accrued fee is included in the supply of the returned snapshot.
+    pub fn sync(
+        &self,
+        aggregate: &mut Account<'info, Aggregate>,
+        crate_mint: &Mint,
+    ) -> Result<Snapshot, ProgramError> {
+        let mut snapshot = self.build_snapshot(aggregate, crate_mint)?;
+        let fee = snapshot
+            .compute_performance_fee(aggregate.high_water_mark, aggregate.performance_fee_bps)?;
+        aggregate.high_water_mark = fee.high_water_mark;
+        if fee.fee_asol.amount == 0 {
+            return Ok(snapshot);
+        }
+
+        snapshot.supply = unwrap_int!(snapshot.supply.checked_add(fee.fee_asol));
+        aggregate.pending_performance_fee =
+            unwrap_int!(aggregate.pending_performance_fee.checked_add(fee.fee_asol));
+
+        emit!(crate::PerformanceFeeEvent {
+            aggregate: aggregate.key(),
+            accrued_sol: fee.accrued_sol.amount,
+            fee_sol: fee.fee_sol.amount,
+            fee_asol: fee.fee_asol.amount,
+            high_water_mark: fee.high_water_mark.amount,
+            timestamp: Clock::get()?.unix_timestamp
+        });
+        Ok(snapshot)
+    }
+}
+
+impl<'info> SyncAndMint<'info> {
+    pub fn sync_and_mint(
+        &mut self,
+        remaining_accounts: &[AccountInfo<'info>],
+        accounting_method: AccountingMethod,
+        deposit_amount: u64,
+        min_mint_amount: u64,
+        options: MintOptions,
+    ) -> ProgramResult {
+        require!(
+            self.mint_asol.stake_pool.accounting_method == accounting_method,
+            AccountingMethodMismatch
+        );
+        let (sync, rest) = SyncAll::load(&self.mint_asol.aggregate, remaining_accounts)?;
+        let optional = MintOptionalAccounts::load(
+            &self.mint_asol.aggregate,
+            self.mint_asol.depositor.key(),
+            options,
+            rest,
+        )?;
+        sync.check_exchange_rates()?;
+
+        let snapshot = sync.sync(&mut self.mint_asol.aggregate, &self.mint_asol.crate_mint)?;
+        let minter = sync.find_stake_pool(self.mint_asol.stake_pool.mint)?;
         self.mint_asol
-            .mint_asol(&snapshot, &self.sync.marinade, deposit_amount)?;
+            .mint_asol(&snapshot, minter, optional, deposit_amount, min_mint_amount)?;
         Ok(())
     }
 }
 
 impl<'info> MintASol<'info> {
-    /// Mints aSOL.
-    pub fn mint_asol<T: Accountant<'info>>(
+    /// Mints aSOL, paying the referral fee and updating the depositor's [Position] if they are passed.
+    /// Fails if fewer than `min_mint_amount` aSOL would be minted.
+    pub fn mint_asol(
         &mut self,
         snapshot: &Snapshot,
-        minter: &T,
+        minter: &SyncStakePool<'info>,
+        mut optional: MintOptionalAccounts<'info>,
         deposit_amount: u64,
+        min_mint_amount: u64,
     ) -> ProgramResult {
         let pool_snapshot = unwrap_or_err!(
             snapshot
@@ -67,19 +262,24 @@ impl<'info> MintASol<'info> {
             PoolNotFoundInSnapshot
         );
 
-        // ignore zero deposit
-        if deposit_amount == 0 {
-            return Ok(());
-        }
+        assert_keys!(
+            *self.stake_pool_tokens,
+            *minter.crate_reserves(),
+            "stake_pool_tokens should be the crate reserves"
+        );
+        require!(deposit_amount > 0, ZeroDeposit);
 
         // compute the amount of tokens to mint
+        pool_snapshot.check_price_band(&self.stake_pool)?;
         let deposit_sol_value = minter.sol_value(deposit_amount)?;
+        snapshot.check_deposit_limits(&self.stake_pool, pool_snapshot, deposit_sol_value)?;
         let mint_amount = snapshot.compute_asol_amount_from_sol(deposit_sol_value)?;
 
-        // ignore zero mint
-        if mint_amount.amount == 0 {
-            return Ok(());
-        }
+        require!(mint_amount.amount > 0, ZeroMint);
+        // the depositor receives the minted amount net of the issue fee
+        let issue_fees = self.crate_token.apply_issue_fee(mint_amount.amount)?;
+        let received_amount = issue_fees.amount;
+        require!(received_amount >= min_mint_amount, MintAmountBelowMinimum);
 
         let signer_seeds: &[&[&[u8]]] = &[&[
             b"Aggregate".as_ref(),
@@ -109,10 +309,8 @@ impl<'info> MintASol<'info> {
                     crate_mint: self.crate_mint.to_account_info(),
                     issue_authority: self.aggregate.to_account_info(),
                     mint_destination: self.mint_destination.to_account_info(),
-
-                    // there are no author/protocol fees, so we pass in garbage here
-                    author_fee_destination: self.mint_destination.to_account_info(),
-                    protocol_fee_destination: self.mint_destination.to_account_info(),
+                    author_fee_destination: self.author_fee_destination.to_account_info(),
+                    protocol_fee_destination: self.protocol_fee_destination.to_account_info(),
 
                     token_program: self.token_program.to_account_info(),
                 },
@@ -121,37 +319,58 @@ impl<'info> MintASol<'info> {
             mint_amount.amount,
         )?;
 
+        // pay the referral fee out of the author share of the issue fee
+        let referral_fee = match &mut optional.referrer {
+            Some(referrer) => referrer.pay_referral_fee(
+                &self.aggregate,
+                self.author_fee_destination.to_account_info(),
+                &self.token_program,
+                issue_fees.author_fee,
+                deposit_sol_value,
+                mint_amount,
+            )?,
+            None => 0,
+        };
+
+        // track the deposited reserves
+        unwrap_or_err!(
+            self.aggregate.find_stake_pool_mut(self.stake_pool.mint),
+            PoolNotFoundInSnapshot
+        )
+        .record_deposit(deposit_amount)?;
+
         // update stats
         let stake_pool_state = &mut self.stake_pool;
-        stake_pool_state.stats.total_amount_deposited = unwrap_int!(stake_pool_state
-            .stats
-            .total_amount_deposited
-            .checked_add(deposit_amount));
-        stake_pool_state.stats.total_amount_minted = ASOL::from(unwrap_int!(stake_pool_state
-            .stats
-            .total_amount_minted
-            .amount
-            .checked_add(mint_amount.amount)));
+        stake_pool_state.record_mint(deposit_amount, mint_amount)?;
 
         // record snapshot
-        stake_pool_state.latest_snapshot.aggregate_balance_sol = snapshot.balance_sol;
-        stake_pool_state.latest_snapshot.aggregate_supply = snapshot.supply;
-        stake_pool_state.latest_snapshot.snapshot = *pool_snapshot;
-        stake_pool_state.latest_snapshot.snapshot_ts = Clock::get()?.unix_timestamp;
+        let now = Clock::get()?.unix_timestamp;
+        stake_pool_state.record_snapshot(snapshot, pool_snapshot, now);
 
         // record aggregate snapshot
         let aggregate = &mut self.aggregate;
         aggregate.latest_snapshot = snapshot.clone();
-        let now = Clock::get()?.unix_timestamp;
         aggregate.latest_snapshot_ts = now;
+        self.snapshot_history.record(snapshot)?;
+
+        // update the depositor's position
+        if let Some(mut position) = optional.position {
+            position.record_deposit(deposit_sol_value, ASOL::from(received_amount), now)?;
+            position.exit(&crate::ID)?;
+        }
 
         // emit event
         emit!(crate::MintASolEvent {
             depositor: self.depositor.key(),
             stake_pool_mint: self.depositor_source.mint,
-            accounting_method: T::METHOD,
+            accounting_method: minter.accounting_method(),
             deposit_amount,
             mint_amount: mint_amount.amount,
+            referrer: optional
+                .referrer
+                .as_ref()
+                .map(|referrer| referrer.referrer.owner),
+            referral_fee,
             timestamp: now
         });
 
@@ -0,0 +1,24 @@
+use crate::{Aggregate, Position};
+use anchor_lang::prelude::*;
+use vipers::assert_keys;
+
+impl Position {
+    /// Loads the [Position] of `owner`, if it was passed.
+    /// The position is optional, and comes after all other remaining accounts.
+    pub fn load_optional<'info>(
+        aggregate: &Account<'info, Aggregate>,
+        owner: Pubkey,
+        accounts: &[AccountInfo<'info>],
+    ) -> Result<Option<Account<'info, Position>>, ProgramError> {
+        match accounts {
+            [] => Ok(None),
+            [position_info] => {
+                let position: Account<Position> = Account::try_from(position_info)?;
+                assert_keys!(position.aggregate, *aggregate, "position.aggregate");
+                assert_keys!(position.owner, owner, "position.owner");
+                Ok(Some(position))
+            }
+            _ => Err(crate::ErrorCode::PoolAccountsMismatch.into()),
+        }
+    }
+}
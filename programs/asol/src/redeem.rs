@@ -0,0 +1,279 @@
+use crate::{
+    mul_div, Position, RedeemASol, RedeemASolPool, Rounding, Snapshot, StakePoolSnapshot, SyncAll,
+    SyncAndRedeem, SyncAndWithdrawSingle, ASOL, MAX_BPS,
+};
+use anchor_lang::{prelude::*, solana_program::account_info::next_account_infos};
+use vipers::{unwrap_int, unwrap_or_err};
+
+/// Number of accounts in a [RedeemASolPool].
+const REDEEM_POOL_ACCOUNTS: usize = 5;
+
+impl<'info> SyncAndRedeem<'info> {
+    pub fn sync_and_redeem(
+        &mut self,
+        remaining_accounts: &[AccountInfo<'info>],
+        redeem_amount: u64,
+    ) -> ProgramResult {
+        // sync accounts come first, followed by the redeem accounts.
+        // exchange rates are not checked, since pro-rata redemptions do not depend on them.
+        let (sync, rest) = SyncAll::load(&self.redeem_asol.aggregate, remaining_accounts)?;
+        let (redeem_accounts, position_accounts) = rest.split_at(rest.len().min(unwrap_int!(
+            sync.stake_pools.len().checked_mul(REDEEM_POOL_ACCOUNTS)
+        )));
+        let position = Position::load_optional(
+            &self.redeem_asol.aggregate,
+            self.redeem_asol.redeemer.key(),
+            position_accounts,
+        )?;
+        let snapshot = sync.sync(
+            &mut self.redeem_asol.aggregate,
+            &self.redeem_asol.crate_mint,
+        )?;
+        self.redeem_asol
+            .redeem_asol(&snapshot, &sync, redeem_accounts, position, redeem_amount)?;
+        Ok(())
+    }
+}
+
+impl<'info> SyncAndWithdrawSingle<'info> {
+    pub fn sync_and_withdraw_single(
+        &mut self,
+        remaining_accounts: &[AccountInfo<'info>],
+        redeem_amount: u64,
+    ) -> ProgramResult {
+        let (sync, rest) = SyncAll::load(&self.redeem_asol.aggregate, remaining_accounts)?;
+        let position = Position::load_optional(
+            &self.redeem_asol.aggregate,
+            self.redeem_asol.redeemer.key(),
+            rest,
+        )?;
+        sync.check_exchange_rates()?;
+
+        let snapshot = sync.sync(
+            &mut self.redeem_asol.aggregate,
+            &self.redeem_asol.crate_mint,
+        )?;
+        self.pool
+            .validate_reserves(sync.find_stake_pool(self.pool.stake_pool.mint)?)?;
+        self.redeem_asol
+            .withdraw_single(&snapshot, &mut self.pool, position, redeem_amount)?;
+        Ok(())
+    }
+}
+
+impl<'info> RedeemASol<'info> {
+    /// Redeems aSOL, updating the redeemer's [Position] if one is passed.
+    pub fn redeem_asol(
+        &mut self,
+        snapshot: &Snapshot,
+        sync: &SyncAll<'info>,
+        remaining_accounts: &[AccountInfo<'info>],
+        position: Option<Account<'info, Position>>,
+        redeem_amount: u64,
+    ) -> ProgramResult {
+        require!(
+            remaining_accounts.len()
+                == unwrap_int!(snapshot.stake_pools.len().checked_mul(REDEEM_POOL_ACCOUNTS)),
+            PoolAccountsMismatch
+        );
+
+        require!(redeem_amount > 0, ZeroRedeem);
+
+        // compute the amount of each stake pool token to withdraw
+        let withdraw_amounts = snapshot.compute_redeem_amounts(ASOL::from(redeem_amount))?;
+
+        self.burn_asol(redeem_amount)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let remaining_accounts_iter = &mut remaining_accounts.iter();
+        for (((pool_meta, pool_sync), pool_snapshot), withdraw_amount) in self
+            .aggregate
+            .stake_pools
+            .iter()
+            .zip(sync.stake_pools.iter())
+            .zip(snapshot.stake_pools.iter())
+            .zip(withdraw_amounts.iter())
+        {
+            let mut pool: RedeemASolPool = Accounts::try_accounts(
+                &crate::ID,
+                &mut next_account_infos(remaining_accounts_iter, REDEEM_POOL_ACCOUNTS)?,
+                &[],
+            )?;
+            pool.validate_for_pool(&self.aggregate, &self.crate_token, pool_meta)?;
+            pool.validate_reserves(pool_sync)?;
+
+            // send the pro-rata share of the stake pool tokens to the redeemer
+            self.withdraw_from_pool(&pool, *withdraw_amount)?;
+            pool.record_withdrawal(snapshot, pool_snapshot, *withdraw_amount, now)?;
+            pool.exit(&crate::ID)?;
+        }
+
+        // track the withdrawn reserves
+        let aggregate = &mut self.aggregate;
+        for (pool_meta, withdraw_amount) in aggregate
+            .stake_pools
+            .iter_mut()
+            .zip(withdraw_amounts.iter())
+        {
+            pool_meta.record_withdrawal(*withdraw_amount)?;
+        }
+
+        // record aggregate snapshot
+        aggregate.latest_snapshot = snapshot.clone();
+        aggregate.latest_snapshot_ts = now;
+        self.snapshot_history.record(snapshot)?;
+
+        // update the redeemer's position
+        if let Some(mut position) = position {
+            let redeem_sol_value =
+                snapshot.compute_sol_amount_from_asol(ASOL::from(redeem_amount))?;
+            position.record_redemption(ASOL::from(redeem_amount), redeem_sol_value)?;
+            position.exit(&crate::ID)?;
+        }
+
+        // emit event
+        emit!(crate::RedeemASolEvent {
+            redeemer: self.redeemer.key(),
+            redeem_amount,
+            withdraw_amounts,
+            timestamp: now
+        });
+
+        Ok(())
+    }
+
+    /// Withdraws aSOL for the tokens of a single stake pool,
+    /// updating the redeemer's [Position] if one is passed.
+    pub fn withdraw_single(
+        &mut self,
+        snapshot: &Snapshot,
+        pool: &mut RedeemASolPool<'info>,
+        position: Option<Account<'info, Position>>,
+        redeem_amount: u64,
+    ) -> ProgramResult {
+        let pool_snapshot = unwrap_or_err!(
+            snapshot
+                .stake_pools
+                .iter()
+                .find(|snap| snap.pool_mint == pool.stake_pool.mint),
+            PoolNotFoundInSnapshot
+        );
+        pool_snapshot.check_price_band(&pool.stake_pool)?;
+
+        require!(redeem_amount > 0, ZeroRedeem);
+
+        // compute the amount of stake pool tokens the aSOL is worth
+        let redeem_sol_value = snapshot.compute_sol_amount_from_asol(ASOL::from(redeem_amount))?;
+        let gross_amount =
+            unwrap_int!(pool_snapshot.pool_token_amount_from_sol(redeem_sol_value, Rounding::Down));
+        let fee_amount = unwrap_int!(mul_div(
+            gross_amount,
+            self.aggregate.single_withdraw_fee_bps.into(),
+            MAX_BPS.into(),
+            Rounding::Up
+        ));
+        let withdraw_amount = unwrap_int!(gross_amount.checked_sub(fee_amount));
+        require!(
+            withdraw_amount <= pool_snapshot.pool_balance,
+            InsufficientPoolBalance
+        );
+
+        self.burn_asol(redeem_amount)?;
+        self.withdraw_from_pool(pool, withdraw_amount)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        pool.record_withdrawal(snapshot, pool_snapshot, withdraw_amount, now)?;
+
+        // track the withdrawn reserves
+        let aggregate = &mut self.aggregate;
+        unwrap_or_err!(
+            aggregate.find_stake_pool_mut(pool.stake_pool.mint),
+            PoolNotFoundInSnapshot
+        )
+        .record_withdrawal(withdraw_amount)?;
+
+        // record aggregate snapshot
+        aggregate.latest_snapshot = snapshot.clone();
+        aggregate.latest_snapshot_ts = now;
+        self.snapshot_history.record(snapshot)?;
+
+        // update the redeemer's position
+        if let Some(mut position) = position {
+            position.record_redemption(ASOL::from(redeem_amount), redeem_sol_value)?;
+            position.exit(&crate::ID)?;
+        }
+
+        // emit event
+        emit!(crate::WithdrawSingleEvent {
+            redeemer: self.redeemer.key(),
+            stake_pool_mint: pool.stake_pool.mint,
+            redeem_amount,
+            withdraw_amount,
+            fee_amount,
+            timestamp: now
+        });
+
+        Ok(())
+    }
+
+    /// Burns the redeemer's aSOL.
+    fn burn_asol(&self, amount: u64) -> ProgramResult {
+        anchor_spl::token::burn(
+            CpiContext::new(
+                self.token_program.to_account_info(),
+                anchor_spl::token::Burn {
+                    mint: self.crate_mint.to_account_info(),
+                    to: self.redeemer_source.to_account_info(),
+                    authority: self.redeemer.to_account_info(),
+                },
+            ),
+            amount,
+        )
+    }
+
+    /// Withdraws stake pool tokens from the crate to the redeemer.
+    fn withdraw_from_pool(&self, pool: &RedeemASolPool<'info>, amount: u64) -> ProgramResult {
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"Aggregate".as_ref(),
+            &self.aggregate.crate_token.to_bytes(),
+            &[self.aggregate.bump],
+        ]];
+        crate_token::cpi::withdraw(
+            CpiContext::new_with_signer(
+                self.crate_token_program.to_account_info(),
+                crate_token::cpi::accounts::Withdraw {
+                    crate_token: self.crate_token.to_account_info(),
+                    crate_underlying: pool.crate_underlying.to_account_info(),
+                    withdraw_authority: self.aggregate.to_account_info(),
+                    withdraw_destination: pool.withdraw_destination.to_account_info(),
+                    author_fee_destination: pool.author_fee_destination.to_account_info(),
+                    protocol_fee_destination: pool.protocol_fee_destination.to_account_info(),
+                    token_program: self.token_program.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            amount,
+        )
+    }
+}
+
+impl<'info> RedeemASolPool<'info> {
+    /// Updates the [crate::StakePool] stats and snapshot after a withdrawal.
+    fn record_withdrawal(
+        &mut self,
+        snapshot: &Snapshot,
+        pool_snapshot: &StakePoolSnapshot,
+        withdraw_amount: u64,
+        now: i64,
+    ) -> ProgramResult {
+        // update stats
+        let stake_pool_state = &mut self.stake_pool;
+        stake_pool_state.total_amount_withdrawn = unwrap_int!(stake_pool_state
+            .total_amount_withdrawn
+            .checked_add(withdraw_amount));
+
+        // record snapshot
+        stake_pool_state.record_snapshot(snapshot, pool_snapshot, now);
+        Ok(())
+    }
+}
@@ -0,0 +1,89 @@
+use crate::{
+    mul_div, Aggregate, MintOptionalAccounts, MintOptions, MintReferrer, Position, Rounding, ASOL,
+    MAX_BPS, SOL,
+};
+use anchor_lang::prelude::*;
+use anchor_spl::token::Token;
+use vipers::unwrap_int;
+
+/// Number of accounts in a [MintReferrer].
+const MINT_REFERRER_ACCOUNTS: usize = 2;
+
+impl<'info> MintOptionalAccounts<'info> {
+    /// Loads the optional accounts of a mint: the [MintReferrer] accounts, followed by the depositor's [Position].
+    /// The [MintOptions] specify which are passed, and no other accounts may follow.
+    pub fn load(
+        aggregate: &Account<'info, Aggregate>,
+        depositor: Pubkey,
+        options: MintOptions,
+        accounts: &[AccountInfo<'info>],
+    ) -> Result<Self, ProgramError> {
+        let num_referrer_accounts = if options.referrer {
+            MINT_REFERRER_ACCOUNTS
+        } else {
+            0
+        };
+        let num_position_accounts = usize::from(options.position);
+        require!(
+            accounts.len() == num_referrer_accounts + num_position_accounts,
+            PoolAccountsMismatch
+        );
+        let (referrer_accounts, position_accounts) = accounts.split_at(num_referrer_accounts);
+
+        let referrer = if options.referrer {
+            let referrer: MintReferrer =
+                Accounts::try_accounts(&crate::ID, &mut &referrer_accounts[..], &[])?;
+            referrer.validate_for_mint(aggregate, depositor)?;
+            Some(referrer)
+        } else {
+            None
+        };
+        let position = Position::load_optional(aggregate, depositor, position_accounts)?;
+        Ok(MintOptionalAccounts { referrer, position })
+    }
+}
+
+impl<'info> MintReferrer<'info> {
+    /// Pays the [Aggregate::referral_fee_bps] share of the author issue fee to the referrer,
+    /// and records the referred deposit. Returns the referral fee.
+    pub fn pay_referral_fee(
+        &mut self,
+        aggregate: &Account<'info, Aggregate>,
+        author_fee_destination: AccountInfo<'info>,
+        token_program: &Program<'info, Token>,
+        author_fee: u64,
+        deposit_sol_value: SOL,
+        mint_amount: ASOL,
+    ) -> Result<u64, ProgramError> {
+        let referral_fee = unwrap_int!(mul_div(
+            author_fee,
+            aggregate.referral_fee_bps.into(),
+            MAX_BPS.into(),
+            Rounding::Down
+        ));
+        if referral_fee > 0 {
+            let signer_seeds: &[&[&[u8]]] = &[&[
+                b"Aggregate".as_ref(),
+                &aggregate.crate_token.to_bytes(),
+                &[aggregate.bump],
+            ]];
+            anchor_spl::token::transfer(
+                CpiContext::new_with_signer(
+                    token_program.to_account_info(),
+                    anchor_spl::token::Transfer {
+                        from: author_fee_destination,
+                        to: self.referrer_fee_destination.to_account_info(),
+                        authority: aggregate.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                referral_fee,
+            )?;
+        }
+
+        self.referrer
+            .record_referral(deposit_sol_value, mint_amount, ASOL::from(referral_fee))?;
+        self.referrer.exit(&crate::ID)?;
+        Ok(referral_fee)
+    }
+}
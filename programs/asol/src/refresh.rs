@@ -0,0 +1,67 @@
+use crate::{Refresh, StakePool, SyncAll};
+use anchor_lang::prelude::*;
+use vipers::assert_keys;
+
+impl<'info> Refresh<'info> {
+    /// Builds a snapshot and records it on the [crate::Aggregate] and every [StakePool].
+    /// Stake pools with a stale exchange rate keep their previous snapshot and are listed in the [crate::RefreshEvent].
+    /// While any stake pool is stale, the aggregate snapshot and the [crate::SnapshotHistory] are not updated either.
+    pub fn refresh(&mut self, remaining_accounts: &[AccountInfo<'info>]) -> ProgramResult {
+        // sync accounts come first, followed by the stake pools in the order of the aggregate
+        let (sync, stake_pools) = SyncAll::load(&self.aggregate, remaining_accounts)?;
+        require!(
+            stake_pools.len() == self.aggregate.stake_pools.len(),
+            PoolAccountsMismatch
+        );
+
+        // a stale stake pool should not block the refresh of the others
+        let clock = Clock::get()?;
+        let stale_pools: Vec<Pubkey> = sync
+            .stake_pools
+            .iter()
+            .filter(|pool| pool.check_exchange_rate(&clock).is_err())
+            .map(|pool| pool.crate_reserves().mint)
+            .collect();
+        // the performance fee is only accrued on fresh exchange rates
+        let snapshot = if stale_pools.is_empty() {
+            sync.sync(&mut self.aggregate, &self.crate_mint)?
+        } else {
+            sync.build_snapshot(&self.aggregate, &self.crate_mint)?
+        };
+
+        let now = clock.unix_timestamp;
+        for ((pool_meta, pool_snapshot), stake_pool_info) in self
+            .aggregate
+            .stake_pools
+            .iter()
+            .zip(snapshot.stake_pools.iter())
+            .zip(stake_pools.iter())
+        {
+            let mut stake_pool: Account<StakePool> = Account::try_from(stake_pool_info)?;
+            assert_keys!(stake_pool.aggregate, self.aggregate, "stake_pool.aggregate");
+            assert_keys!(stake_pool.mint, pool_meta.mint, "stake_pool.mint");
+            if stale_pools.contains(&pool_meta.mint) {
+                continue;
+            }
+            stake_pool.record_snapshot(&snapshot, pool_snapshot, now);
+            stake_pool.exit(&crate::ID)?;
+        }
+
+        // record aggregate snapshot, unless it prices a stale stake pool
+        let aggregate = &mut self.aggregate;
+        if stale_pools.is_empty() {
+            aggregate.latest_snapshot = snapshot.clone();
+            aggregate.latest_snapshot_ts = now;
+            self.snapshot_history.record(&snapshot)?;
+        }
+
+        emit!(crate::RefreshEvent {
+            aggregate: aggregate.key(),
+            snapshot,
+            stale_pools,
+            timestamp: now
+        });
+
+        Ok(())
+    }
+}
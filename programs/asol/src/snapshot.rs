@@ -1,10 +1,10 @@
-use vipers::{assert_keys, unwrap_int};
+use vipers::{assert_keys, invariant, unwrap_int};
 
 use crate::{
-    accounting::Accountant, StakePoolMeta, ASOL, MIN_LIQUIDITY_FOR_EXACT_CALCULATION, SOL,
+    accounting::Accountant, mul_div, ExchangeRate, Rounding, StakePool, StakePoolMeta, ASOL,
+    MAX_BPS, MIN_LIQUIDITY_FOR_EXACT_CALCULATION, SOL,
 };
 use anchor_lang::{prelude::*, solana_program::native_token::LAMPORTS_PER_SOL};
-use num_traits::ToPrimitive;
 
 /// A balance snapshot.
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
@@ -28,28 +28,226 @@ pub struct StakePoolSnapshot {
     pub sol_for_1e9: SOL,
 }
 
+/// The performance fee on the yield earned since the high-water mark.
+/// See [Snapshot::compute_performance_fee].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PerformanceFee {
+    /// Yield earned by aSOL holders since the high-water mark.
+    pub accrued_sol: SOL,
+    /// Share of the yield charged as the fee, or the leftover reserves swept to the treasury.
+    pub fee_sol: SOL,
+    /// Amount of [ASOL] to mint to the treasury, worth [PerformanceFee::fee_sol].
+    pub fee_asol: ASOL,
+    /// The new high-water mark, as the [SOL] value of 1e9 [ASOL].
+    pub high_water_mark: SOL,
+}
+
 impl Snapshot {
-    /// Gets the number of [ASOL] corresponding to an amount of [SOL].
+    /// The [ExchangeRate] of the aggregate.
+    pub fn exchange_rate(&self) -> ExchangeRate {
+        // if no aSOL is outstanding and less than 1 SOL is left, the price is equal to SOL price.
+        // larger leftover balances are swept to the treasury first, see [Snapshot::compute_performance_fee].
+        if self.supply.amount == 0 && self.balance_sol.amount <= MIN_LIQUIDITY_FOR_EXACT_CALCULATION
+        {
+            return ExchangeRate::ONE;
+        }
+        ExchangeRate::new(self.balance_sol, self.supply)
+    }
+
+    /// The [SOL] value of 1e9 [ASOL], rounded down.
+    pub fn asol_price(&self) -> Result<SOL, ProgramError> {
+        Ok(unwrap_int!(self
+            .exchange_rate()
+            .asol_to_sol(ASOL::from(LAMPORTS_PER_SOL), Rounding::Down)))
+    }
+
+    /// Computes the performance fee on the increase of [Snapshot::asol_price] over the `high_water_mark`.
+    /// The fee is minted as [ASOL] such that, after minting, the treasury's share of the aggregate is worth the fee.
+    /// An unset (zero) high-water mark is initialized to the current price without charging a fee.
+    ///
+    /// If no aSOL is outstanding, the high-water mark is reset to 1 SOL. A balance above
+    /// [MIN_LIQUIDITY_FOR_EXACT_CALCULATION] was left behind by the last redemption, e.g. as the single withdraw fee,
+    /// so all of it is swept to the treasury at 1 SOL per aSOL.
+    pub fn compute_performance_fee(
+        &self,
+        high_water_mark: SOL,
+        performance_fee_bps: u16,
+    ) -> Result<PerformanceFee, ProgramError> {
+        if self.supply.amount == 0 {
+            let leftover_sol = if self.balance_sol.amount > MIN_LIQUIDITY_FOR_EXACT_CALCULATION {
+                self.balance_sol
+            } else {
+                SOL::from(0)
+            };
+            return Ok(PerformanceFee {
+                accrued_sol: SOL::from(0),
+                fee_sol: leftover_sol,
+                fee_asol: ASOL::from(leftover_sol.amount),
+                high_water_mark: SOL::from(LAMPORTS_PER_SOL),
+            });
+        }
+
+        let price = self.asol_price()?;
+        if high_water_mark.amount == 0 || price <= high_water_mark {
+            return Ok(PerformanceFee {
+                high_water_mark: high_water_mark.max(price),
+                ..Default::default()
+            });
+        }
+
+        let accrued_sol = unwrap_int!(unwrap_int!(price.checked_sub(high_water_mark))
+            .checked_mul_div(self.supply.amount, LAMPORTS_PER_SOL, Rounding::Down));
+        let fee_sol = unwrap_int!(accrued_sol.checked_mul_div(
+            performance_fee_bps.into(),
+            MAX_BPS.into(),
+            Rounding::Down
+        ));
+        // fee_asol / (supply + fee_asol) = fee_sol / balance_sol
+        let fee_asol = unwrap_int!(ExchangeRate::new(
+            unwrap_int!(self.balance_sol.checked_sub(fee_sol)),
+            self.supply
+        )
+        .sol_to_asol(fee_sol, Rounding::Down));
+
+        let after_fee = Snapshot {
+            supply: unwrap_int!(self.supply.checked_add(fee_asol)),
+            ..self.clone()
+        };
+        Ok(PerformanceFee {
+            accrued_sol,
+            fee_sol,
+            fee_asol,
+            high_water_mark: after_fee.asol_price()?.max(high_water_mark),
+        })
+    }
+
+    /// Gets the number of [ASOL] to mint for an amount of [SOL], rounded down.
     pub fn compute_asol_amount_from_sol(&self, sol_amount: SOL) -> Result<ASOL, ProgramError> {
-        // if less than 1 SOL is staked, the price is equal to SOL price.
-        // this is to avoid precision errors with tiny balances.
+        Ok(unwrap_int!(self
+            .exchange_rate()
+            .sol_to_asol(sol_amount, Rounding::Down)))
+    }
+
+    /// Gets the number of [SOL] to pay out for an amount of [ASOL], rounded down.
+    /// This is the inverse of [Snapshot::compute_asol_amount_from_sol].
+    pub fn compute_sol_amount_from_asol(&self, asol_amount: ASOL) -> Result<SOL, ProgramError> {
+        Ok(unwrap_int!(self
+            .exchange_rate()
+            .asol_to_sol(asol_amount, Rounding::Down)))
+    }
+
+    /// Computes the amount of each stake pool's tokens that [ASOL] is redeemable for.
+    /// Amounts are pro-rata to the pool balances, rounded down, and are returned in the order of [Snapshot::stake_pools].
+    pub fn compute_redeem_amounts(&self, asol_amount: ASOL) -> Result<Vec<u64>, ProgramError> {
+        invariant!(
+            asol_amount <= self.supply,
+            "cannot redeem more than the supply"
+        );
+        self.stake_pools
+            .iter()
+            .map(|pool| {
+                Ok(unwrap_int!(mul_div(
+                    pool.pool_balance,
+                    asol_amount.amount,
+                    self.supply.amount,
+                    Rounding::Down
+                )))
+            })
+            .collect()
+    }
+
+    /// Finds the index of the eligible stake pool with the most headroom, i.e. its max weight minus its current weight.
+    /// `max_weight_bps` returns the max weight of a stake pool, or [None] if the stake pool is not eligible.
+    /// Ties are broken by the order of [Snapshot::stake_pools].
+    pub fn find_pool_with_most_headroom(
+        &self,
+        max_weight_bps: impl Fn(&StakePoolSnapshot) -> Option<u16>,
+    ) -> Result<Option<usize>, ProgramError> {
+        // an empty aggregate has no weights, so the headroom is the max weight
+        let balance_sol = self.balance_sol.amount.max(1);
+
+        let mut most: Option<(usize, i128)> = None;
+        for (index, pool) in self.stake_pools.iter().enumerate() {
+            let max_weight_bps = match max_weight_bps(pool) {
+                Some(max_weight_bps) => max_weight_bps,
+                None => continue,
+            };
+            // headroom scaled by `balance_sol * MAX_BPS`, to compare without rounding
+            let pool_balance_sol = unwrap_int!(pool.pool_balance_sol(Rounding::Down));
+            let headroom = unwrap_int!((i128::from(max_weight_bps) * i128::from(balance_sol))
+                .checked_sub(i128::from(pool_balance_sol) * i128::from(MAX_BPS)));
+            let is_most = match most {
+                Some((_, most_headroom)) => headroom > most_headroom,
+                None => true,
+            };
+            if is_most {
+                most = Some((index, headroom));
+            }
+        }
+        Ok(most.map(|(index, _)| index))
+    }
+
+    /// Checks that depositing [SOL] into a [StakePool] keeps it within its deposit cap and max weight.
+    /// The max weight is not enforced until the aggregate holds enough liquidity, to allow bootstrapping.
+    pub fn check_deposit_limits(
+        &self,
+        pool: &StakePool,
+        pool_snapshot: &StakePoolSnapshot,
+        deposit_sol_value: SOL,
+    ) -> ProgramResult {
+        // round the pool balance up, so the limits are never exceeded
+        let pool_balance_sol = unwrap_int!(pool_snapshot
+            .pool_balance_sol(Rounding::Up)
+            .and_then(|v| v.checked_add(deposit_sol_value.amount)));
+        require!(
+            pool_balance_sol <= pool.deposit_cap.amount,
+            DepositCapExceeded
+        );
+
         if self.balance_sol.amount <= MIN_LIQUIDITY_FOR_EXACT_CALCULATION {
-            return Ok(ASOL::from(sol_amount.amount));
+            return Ok(());
         }
-        Ok(unwrap_int!(
-            sol_amount.checked_mul_asol(self.supply, self.balance_sol)
-        ))
+        let balance_sol = unwrap_int!(self
+            .balance_sol
+            .amount
+            .checked_add(deposit_sol_value.amount));
+        require!(
+            unwrap_int!((pool_balance_sol as u128).checked_mul(MAX_BPS.into()))
+                <= unwrap_int!((balance_sol as u128).checked_mul(pool.max_weight_bps.into())),
+            MaxWeightExceeded
+        );
+        Ok(())
     }
 }
 
 impl StakePoolSnapshot {
     /// The [SOL] value of the pool's balance, based on the price.
-    pub fn pool_balance_sol(&self) -> Option<u64> {
-        self.sol_for_1e9
-            .to_u128()
-            .checked_mul(self.pool_balance.into())?
-            .checked_div(LAMPORTS_PER_SOL.into())?
-            .to_u64()
+    pub fn pool_balance_sol(&self, rounding: Rounding) -> Option<u64> {
+        mul_div(
+            self.pool_balance,
+            self.sol_for_1e9.amount,
+            LAMPORTS_PER_SOL,
+            rounding,
+        )
+    }
+
+    /// The amount of pool tokens worth the given amount of [SOL], based on the price.
+    pub fn pool_token_amount_from_sol(&self, sol_amount: SOL, rounding: Rounding) -> Option<u64> {
+        mul_div(
+            sol_amount.amount,
+            LAMPORTS_PER_SOL,
+            self.sol_for_1e9.amount,
+            rounding,
+        )
+    }
+
+    /// Checks that the price of the stake pool is within the band set on its [StakePool].
+    pub fn check_price_band(&self, pool: &StakePool) -> ProgramResult {
+        require!(
+            pool.min_sol_for_1e9 <= self.sol_for_1e9 && self.sol_for_1e9 <= pool.max_sol_for_1e9,
+            PriceOutOfBand
+        );
+        Ok(())
     }
 
     /// Creates a pool snapshot from an [Accountant].
@@ -62,17 +260,24 @@ impl StakePoolSnapshot {
             accountant.crate_reserves().mint,
             format!("incorrect pool mint for {:?}", T::METHOD)
         );
-        Self::try_from_accountant_unchecked(accountant)
+        assert_keys!(
+            pool.pool_state,
+            accountant.pool_state(),
+            format!("incorrect pool state for {:?}", T::METHOD)
+        );
+        Self::try_from_accountant_unchecked(pool, accountant)
     }
 
     /// Creates a pool snapshot from an accountant.
+    /// Only the reserves tracked by the [StakePoolMeta] are counted, ignoring any tokens donated to the Crate.
     fn try_from_accountant_unchecked<'info, T: Accountant<'info>>(
+        pool: &StakePoolMeta,
         accountant: &T,
     ) -> Result<StakePoolSnapshot, ProgramError> {
         let reserves = accountant.crate_reserves();
         Ok(StakePoolSnapshot {
             pool_mint: reserves.mint,
-            pool_balance: reserves.amount,
+            pool_balance: pool.accounted_balance(reserves.amount),
             sol_for_1e9: accountant.sol_value(LAMPORTS_PER_SOL)?,
         })
     }
@@ -105,8 +310,8 @@ mod tests {
             .compute_asol_amount_from_sol(SOL::from(1_100_000))
             .unwrap();
 
-        // it's below the minimum
-        assert_eq!(output, ASOL::from(1_100_000));
+        // it's below the minimum, but aSOL is outstanding so it is priced exactly
+        assert_eq!(output, ASOL::from(1_000_000));
     }
 
     #[test]
@@ -131,8 +336,8 @@ mod tests {
             .compute_asol_amount_from_sol(SOL::from(1_100_000))
             .unwrap();
 
-        // it's below the minimum
-        assert_eq!(output, ASOL::from(1_100_000));
+        // it's below the minimum, but aSOL is outstanding so it is priced exactly
+        assert_eq!(output, ASOL::from(1_000_000));
     }
 
     #[test]
@@ -161,6 +366,454 @@ mod tests {
         assert_eq!(output, ASOL::from(1_000_000));
     }
 
+    #[test]
+    fn test_compute_redeem_amounts() {
+        let agg = Snapshot {
+            balance_sol: SOL::from(3_300_000_000),
+            supply: ASOL::from(3_000_000_000),
+            stake_pools: vec![
+                StakePoolSnapshot {
+                    pool_mint: lido_stsol::ID,
+                    pool_balance: 1_000_000_000,
+                    sol_for_1e9: SOL::from(1_100_000_000),
+                },
+                StakePoolSnapshot {
+                    pool_mint: marinade_msol::ID,
+                    pool_balance: 2_000_000_000,
+                    sol_for_1e9: SOL::from(1_100_000_000),
+                },
+            ],
+        };
+        let output = agg.compute_redeem_amounts(ASOL::from(300_000_000)).unwrap();
+        assert_eq!(output, vec![100_000_000, 200_000_000]);
+    }
+
+    #[test]
+    fn test_compute_redeem_amounts_rounds_down() {
+        let agg = Snapshot {
+            balance_sol: SOL::from(3),
+            supply: ASOL::from(3),
+            stake_pools: vec![StakePoolSnapshot {
+                pool_mint: lido_stsol::ID,
+                pool_balance: 2,
+                sol_for_1e9: SOL::from(LAMPORTS_PER_SOL),
+            }],
+        };
+        let output = agg.compute_redeem_amounts(ASOL::from(1)).unwrap();
+        assert_eq!(output, vec![0]);
+    }
+
+    #[test]
+    fn test_compute_redeem_amounts_exceeds_supply() {
+        let agg = Snapshot {
+            supply: ASOL::from(1_000),
+            ..Default::default()
+        };
+        assert!(agg.compute_redeem_amounts(ASOL::from(1_001)).is_err());
+    }
+
+    #[test]
+    fn test_compute_sol_amount_from_asol_below_minimum() {
+        // redemptions left less than 1 SOL, but the remaining holders still own all of it
+        let agg = Snapshot {
+            balance_sol: SOL::from(2_200_000),
+            supply: ASOL::from(2_000_000),
+            ..Default::default()
+        };
+        let output = agg
+            .compute_sol_amount_from_asol(ASOL::from(1_000_000))
+            .unwrap();
+        assert_eq!(output, SOL::from(1_100_000));
+    }
+
+    #[test]
+    fn test_exchange_rate_pegged_when_empty() {
+        let agg = Snapshot {
+            balance_sol: SOL::from(MIN_LIQUIDITY_FOR_EXACT_CALCULATION),
+            supply: ASOL::from(0),
+            ..Default::default()
+        };
+        assert_eq!(agg.exchange_rate(), ExchangeRate::ONE);
+        assert_eq!(
+            agg.compute_asol_amount_from_sol(SOL::from(1_000)).unwrap(),
+            ASOL::from(1_000)
+        );
+    }
+
+    #[test]
+    fn test_exchange_rate_exact_below_minimum() {
+        // redemptions left less than 1 SOL, with the supply not equal to the balance
+        let agg = Snapshot {
+            balance_sol: SOL::from(500_000_000),
+            supply: ASOL::from(250_000_000),
+            ..Default::default()
+        };
+        assert_eq!(
+            agg.exchange_rate(),
+            ExchangeRate::new(SOL::from(500_000_000), ASOL::from(250_000_000))
+        );
+        // a deposit does not dilute the remaining holders
+        assert_eq!(
+            agg.compute_asol_amount_from_sol(SOL::from(100_000_000))
+                .unwrap(),
+            ASOL::from(50_000_000)
+        );
+        // a withdrawal does not drain them
+        assert_eq!(
+            agg.compute_sol_amount_from_asol(ASOL::from(50_000_000))
+                .unwrap(),
+            SOL::from(100_000_000)
+        );
+    }
+
+    #[test]
+    fn test_compute_sol_amount_from_asol_inverse() {
+        let agg = Snapshot {
+            balance_sol: SOL::from(3_300_000_000),
+            supply: ASOL::from(3_000_000_000),
+            ..Default::default()
+        };
+        let asol = agg
+            .compute_asol_amount_from_sol(SOL::from(1_100_000))
+            .unwrap();
+        assert_eq!(asol, ASOL::from(1_000_000));
+        let sol = agg.compute_sol_amount_from_asol(asol).unwrap();
+        assert_eq!(sol, SOL::from(1_100_000));
+    }
+
+    fn limited_pool(max_weight_bps: u16, deposit_cap: u64) -> StakePool {
+        StakePool {
+            max_weight_bps,
+            deposit_cap: SOL::from(deposit_cap),
+            ..Default::default()
+        }
+    }
+
+    fn two_pool_snapshot() -> Snapshot {
+        Snapshot {
+            balance_sol: SOL::from(4_000_000_000),
+            supply: ASOL::from(4_000_000_000),
+            stake_pools: vec![
+                StakePoolSnapshot {
+                    pool_mint: lido_stsol::ID,
+                    pool_balance: 1_000_000_000,
+                    sol_for_1e9: SOL::from(LAMPORTS_PER_SOL),
+                },
+                StakePoolSnapshot {
+                    pool_mint: marinade_msol::ID,
+                    pool_balance: 3_000_000_000,
+                    sol_for_1e9: SOL::from(LAMPORTS_PER_SOL),
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_check_deposit_limits_unlimited() {
+        let agg = two_pool_snapshot();
+        agg.check_deposit_limits(
+            &limited_pool(MAX_BPS, u64::MAX),
+            &agg.stake_pools[1],
+            SOL::from(100_000_000_000),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_check_deposit_limits_cap() {
+        let agg = two_pool_snapshot();
+        let pool = limited_pool(MAX_BPS, 2_000_000_000);
+        agg.check_deposit_limits(&pool, &agg.stake_pools[0], SOL::from(1_000_000_000))
+            .unwrap();
+        assert_eq!(
+            agg.check_deposit_limits(&pool, &agg.stake_pools[0], SOL::from(1_000_000_001)),
+            Err(ErrorCode::DepositCapExceeded.into())
+        );
+    }
+
+    #[test]
+    fn test_check_deposit_limits_max_weight() {
+        let agg = two_pool_snapshot();
+        let pool = limited_pool(5_000, u64::MAX);
+        // 3 of 6 SOL is exactly 50%
+        agg.check_deposit_limits(&pool, &agg.stake_pools[0], SOL::from(2_000_000_000))
+            .unwrap();
+        assert_eq!(
+            agg.check_deposit_limits(&pool, &agg.stake_pools[0], SOL::from(2_000_000_001)),
+            Err(ErrorCode::MaxWeightExceeded.into())
+        );
+        // the larger pool is already over the max weight
+        assert_eq!(
+            agg.check_deposit_limits(&pool, &agg.stake_pools[1], SOL::from(1)),
+            Err(ErrorCode::MaxWeightExceeded.into())
+        );
+    }
+
+    #[test]
+    fn test_check_deposit_limits_max_weight_bootstrap() {
+        let agg = Snapshot::default();
+        agg.check_deposit_limits(
+            &limited_pool(1_000, u64::MAX),
+            &StakePoolSnapshot {
+                sol_for_1e9: SOL::from(LAMPORTS_PER_SOL),
+                ..Default::default()
+            },
+            SOL::from(100_000_000_000),
+        )
+        .unwrap();
+    }
+
+    fn fee_snapshot(balance_sol: u64, supply: u64) -> Snapshot {
+        Snapshot {
+            balance_sol: SOL::from(balance_sol),
+            supply: ASOL::from(supply),
+            stake_pools: vec![],
+        }
+    }
+
+    #[test]
+    fn test_performance_fee_initializes_high_water_mark() {
+        let agg = fee_snapshot(110 * LAMPORTS_PER_SOL, 100 * LAMPORTS_PER_SOL);
+        let fee = agg.compute_performance_fee(SOL::from(0), 1_000).unwrap();
+        assert_eq!(
+            fee,
+            PerformanceFee {
+                high_water_mark: SOL::from(1_100_000_000),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_performance_fee_below_high_water_mark() {
+        let agg = fee_snapshot(110 * LAMPORTS_PER_SOL, 100 * LAMPORTS_PER_SOL);
+        let fee = agg
+            .compute_performance_fee(SOL::from(1_200_000_000), 1_000)
+            .unwrap();
+        assert_eq!(
+            fee,
+            PerformanceFee {
+                high_water_mark: SOL::from(1_200_000_000),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_performance_fee_on_yield() {
+        // price went from 1 to 1.1 SOL per aSOL, so 10 SOL of yield was earned
+        let agg = fee_snapshot(110 * LAMPORTS_PER_SOL, 100 * LAMPORTS_PER_SOL);
+        let fee = agg
+            .compute_performance_fee(SOL::from(LAMPORTS_PER_SOL), 1_000)
+            .unwrap();
+        assert_eq!(fee.accrued_sol, SOL::from(10 * LAMPORTS_PER_SOL));
+        assert_eq!(fee.fee_sol, SOL::from(LAMPORTS_PER_SOL));
+        // 1 SOL is worth 100/109 aSOL at the price net of the fee
+        assert_eq!(fee.fee_asol, ASOL::from(917_431_192));
+
+        // the treasury's aSOL is worth no more than the fee
+        let after_fee = fee_snapshot(
+            110 * LAMPORTS_PER_SOL,
+            100 * LAMPORTS_PER_SOL + fee.fee_asol.amount,
+        );
+        assert_eq!(fee.high_water_mark, after_fee.asol_price().unwrap());
+        assert_eq!(fee.high_water_mark, SOL::from(1_090_000_000));
+        let treasury_sol = after_fee
+            .compute_sol_amount_from_asol(fee.fee_asol)
+            .unwrap();
+        assert!(treasury_sol <= fee.fee_sol);
+        assert_eq!(treasury_sol, SOL::from(999_999_999));
+
+        // a second sync at the same balances charges nothing
+        let fee = after_fee
+            .compute_performance_fee(fee.high_water_mark, 1_000)
+            .unwrap();
+        assert_eq!(fee.fee_asol, ASOL::from(0));
+    }
+
+    #[test]
+    fn test_performance_fee_sweeps_leftover_reserves() {
+        // the last aSOL was burned, leaving 2 SOL of single withdraw fees behind
+        let agg = fee_snapshot(2 * LAMPORTS_PER_SOL, 0);
+        let fee = agg
+            .compute_performance_fee(SOL::from(1_200_000_000), 1_000)
+            .unwrap();
+        assert_eq!(
+            fee,
+            PerformanceFee {
+                accrued_sol: SOL::from(0),
+                fee_sol: SOL::from(2 * LAMPORTS_PER_SOL),
+                fee_asol: ASOL::from(2 * LAMPORTS_PER_SOL),
+                high_water_mark: SOL::from(LAMPORTS_PER_SOL),
+            }
+        );
+
+        // once swept, the next deposit mints at 1 SOL per aSOL instead of failing
+        let after_fee = fee_snapshot(2 * LAMPORTS_PER_SOL, fee.fee_asol.amount);
+        assert_eq!(
+            after_fee
+                .compute_asol_amount_from_sol(SOL::from(LAMPORTS_PER_SOL))
+                .unwrap(),
+            ASOL::from(LAMPORTS_PER_SOL)
+        );
+    }
+
+    #[test]
+    fn test_performance_fee_leaves_dust() {
+        let agg = fee_snapshot(MIN_LIQUIDITY_FOR_EXACT_CALCULATION, 0);
+        let fee = agg
+            .compute_performance_fee(SOL::from(1_200_000_000), 1_000)
+            .unwrap();
+        assert_eq!(
+            fee,
+            PerformanceFee {
+                high_water_mark: SOL::from(LAMPORTS_PER_SOL),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_performance_fee_zero_bps_advances_high_water_mark() {
+        let agg = fee_snapshot(110 * LAMPORTS_PER_SOL, 100 * LAMPORTS_PER_SOL);
+        let fee = agg
+            .compute_performance_fee(SOL::from(LAMPORTS_PER_SOL), 0)
+            .unwrap();
+        assert_eq!(fee.fee_asol, ASOL::from(0));
+        assert_eq!(fee.high_water_mark, SOL::from(1_100_000_000));
+    }
+
+    #[test]
+    fn test_check_price_band() {
+        let pool = StakePool {
+            min_sol_for_1e9: SOL::from(LAMPORTS_PER_SOL),
+            max_sol_for_1e9: SOL::from(2 * LAMPORTS_PER_SOL),
+            ..Default::default()
+        };
+        let snap = |sol_for_1e9: u64| StakePoolSnapshot {
+            sol_for_1e9: SOL::from(sol_for_1e9),
+            ..Default::default()
+        };
+        snap(LAMPORTS_PER_SOL).check_price_band(&pool).unwrap();
+        snap(2 * LAMPORTS_PER_SOL).check_price_band(&pool).unwrap();
+        assert_eq!(
+            snap(LAMPORTS_PER_SOL - 1).check_price_band(&pool),
+            Err(ErrorCode::PriceOutOfBand.into())
+        );
+        assert_eq!(
+            snap(2 * LAMPORTS_PER_SOL + 1).check_price_band(&pool),
+            Err(ErrorCode::PriceOutOfBand.into())
+        );
+    }
+
+    #[test]
+    fn test_find_pool_with_most_headroom() {
+        let agg = two_pool_snapshot();
+        assert_eq!(
+            agg.find_pool_with_most_headroom(|_| Some(MAX_BPS)).unwrap(),
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn test_find_pool_with_most_headroom_uses_price() {
+        let mut agg = two_pool_snapshot();
+        // 1 stSOL is now worth more than 3 mSOL
+        agg.stake_pools[0].sol_for_1e9 = SOL::from(4 * LAMPORTS_PER_SOL);
+        agg.balance_sol = SOL::from(7_000_000_000);
+        assert_eq!(
+            agg.find_pool_with_most_headroom(|_| Some(MAX_BPS)).unwrap(),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn test_find_pool_with_most_headroom_uses_max_weights() {
+        let agg = two_pool_snapshot();
+        // the stSOL pool is at 25% with a max of 30%, the mSOL pool at 75% with a max of 100%
+        let max_weight_bps = |pool: &StakePoolSnapshot| {
+            Some(if pool.pool_mint == lido_stsol::ID {
+                3_000
+            } else {
+                MAX_BPS
+            })
+        };
+        assert_eq!(
+            agg.find_pool_with_most_headroom(max_weight_bps).unwrap(),
+            Some(1)
+        );
+
+        // a pool at its max weight has no headroom
+        let max_weight_bps = |pool: &StakePoolSnapshot| {
+            Some(if pool.pool_mint == lido_stsol::ID {
+                2_500
+            } else {
+                7_500
+            })
+        };
+        assert_eq!(
+            agg.find_pool_with_most_headroom(max_weight_bps).unwrap(),
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn test_find_pool_with_most_headroom_empty() {
+        let mut agg = two_pool_snapshot();
+        agg.balance_sol = SOL::from(0);
+        agg.stake_pools[0].pool_balance = 0;
+        agg.stake_pools[1].pool_balance = 0;
+        let max_weight_bps = |pool: &StakePoolSnapshot| {
+            Some(if pool.pool_mint == lido_stsol::ID {
+                3_000
+            } else {
+                7_000
+            })
+        };
+        assert_eq!(
+            agg.find_pool_with_most_headroom(max_weight_bps).unwrap(),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn test_find_pool_with_most_headroom_eligible() {
+        let agg = two_pool_snapshot();
+        assert_eq!(
+            agg.find_pool_with_most_headroom(
+                |pool| Some(MAX_BPS).filter(|_| pool.pool_mint != lido_stsol::ID)
+            )
+            .unwrap(),
+            Some(1)
+        );
+        assert_eq!(agg.find_pool_with_most_headroom(|_| None).unwrap(), None);
+    }
+
+    #[test]
+    fn test_pool_token_amount_from_sol() {
+        let snap = StakePoolSnapshot {
+            pool_balance: 1_000_000,
+            sol_for_1e9: SOL::from(1_100_000_000),
+            ..Default::default()
+        };
+        assert_eq!(
+            snap.pool_token_amount_from_sol(SOL::from(1_100_000), Rounding::Down)
+                .unwrap(),
+            1_000_000
+        );
+    }
+
+    #[test]
+    fn test_pool_token_amount_from_sol_zero_price() {
+        let snap = StakePoolSnapshot {
+            sol_for_1e9: SOL::from(0),
+            ..Default::default()
+        };
+        assert!(snap
+            .pool_token_amount_from_sol(SOL::from(1), Rounding::Down)
+            .is_none());
+    }
+
     #[test]
     fn test_pool_balance_sol_empty() {
         let snap = StakePoolSnapshot {
@@ -168,7 +821,7 @@ mod tests {
             sol_for_1e9: SOL::from(LAMPORTS_PER_SOL),
             ..Default::default()
         };
-        assert_eq!(snap.pool_balance_sol().unwrap(), 0);
+        assert_eq!(snap.pool_balance_sol(Rounding::Down).unwrap(), 0);
     }
 
     #[test]
@@ -178,7 +831,7 @@ mod tests {
             sol_for_1e9: SOL::from(LAMPORTS_PER_SOL),
             ..Default::default()
         };
-        assert_eq!(snap.pool_balance_sol().unwrap(), 1_000_000);
+        assert_eq!(snap.pool_balance_sol(Rounding::Down).unwrap(), 1_000_000);
     }
 
     #[test]
@@ -188,7 +841,7 @@ mod tests {
             sol_for_1e9: SOL::from(1_100_000_000),
             ..Default::default()
         };
-        assert_eq!(snap.pool_balance_sol().unwrap(), 1_100_000);
+        assert_eq!(snap.pool_balance_sol(Rounding::Down).unwrap(), 1_100_000);
     }
 
     #[test]
@@ -198,6 +851,75 @@ mod tests {
             sol_for_1e9: SOL::from(0),
             ..Default::default()
         };
-        assert_eq!(snap.pool_balance_sol().unwrap(), 0);
+        assert_eq!(snap.pool_balance_sol(Rounding::Down).unwrap(), 0);
+    }
+
+    /// Builds a single-pool snapshot at a price of 1 SOL per token,
+    /// given the actual amount of tokens held by the Crate.
+    fn reserves_snapshot(meta: &StakePoolMeta, reserves_amount: u64, supply: u64) -> Snapshot {
+        let pool_balance = meta.accounted_balance(reserves_amount);
+        Snapshot {
+            balance_sol: SOL::from(pool_balance),
+            supply: ASOL::from(supply),
+            stake_pools: vec![StakePoolSnapshot {
+                pool_mint: marinade_msol::ID,
+                pool_balance,
+                sol_for_1e9: SOL::from(LAMPORTS_PER_SOL),
+            }],
+        }
+    }
+
+    #[test]
+    fn test_donation_does_not_cross_min_liquidity() {
+        // attacker is the first depositor and mints 1 lamport of aSOL
+        let mut meta = StakePoolMeta::default();
+        meta.record_deposit(1).unwrap();
+
+        // attacker donates 2 SOL worth of tokens directly to the reserves
+        let agg = reserves_snapshot(&meta, 1 + 2 * LAMPORTS_PER_SOL, 1);
+        assert_eq!(agg.balance_sol, SOL::from(1));
+
+        // the victim still receives aSOL at the 1:1 price
+        assert_eq!(
+            agg.compute_asol_amount_from_sol(SOL::from(LAMPORTS_PER_SOL))
+                .unwrap(),
+            ASOL::from(LAMPORTS_PER_SOL)
+        );
+    }
+
+    #[test]
+    fn test_donation_does_not_inflate_price() {
+        // attacker is the first depositor, just above the minimum liquidity
+        let deposit = MIN_LIQUIDITY_FOR_EXACT_CALCULATION + 1;
+        let mut meta = StakePoolMeta::default();
+        meta.record_deposit(deposit).unwrap();
+
+        // attacker donates 1,000 SOL worth of tokens directly to the reserves
+        let agg = reserves_snapshot(&meta, deposit + 1_000 * LAMPORTS_PER_SOL, deposit);
+        assert_eq!(agg.stake_pools[0].pool_balance, deposit);
+
+        // the victim's deposit is priced as if no donation happened
+        assert_eq!(
+            agg.compute_asol_amount_from_sol(SOL::from(LAMPORTS_PER_SOL))
+                .unwrap(),
+            ASOL::from(LAMPORTS_PER_SOL)
+        );
+
+        // redemptions only pay out the accounted reserves
+        assert_eq!(
+            agg.compute_redeem_amounts(ASOL::from(deposit)).unwrap(),
+            vec![deposit]
+        );
+    }
+
+    #[test]
+    fn test_accounted_balance_tracks_withdrawals() {
+        let mut meta = StakePoolMeta::default();
+        meta.record_deposit(5_000).unwrap();
+        meta.record_withdrawal(2_000).unwrap();
+        assert_eq!(meta.accounted_balance(10_000), 3_000);
+        // never account for more than the Crate actually holds
+        assert_eq!(meta.accounted_balance(1_000), 1_000);
+        assert!(meta.record_withdrawal(3_001).is_err());
     }
 }
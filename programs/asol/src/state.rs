@@ -1,9 +1,24 @@
 use anchor_lang::prelude::*;
+use vipers::unwrap_int;
 
 use crate::{Snapshot, StakePoolSnapshot, ASOL, SOL};
 
+/// Pauses minting aSOL.
+pub const PAUSE_MINT: u8 = 1 << 0;
+/// Pauses redeeming aSOL. On a [StakePool], this only pauses [crate::asol::withdraw_single] from that pool.
+pub const PAUSE_REDEEM: u8 = 1 << 1;
+/// Pauses adding stake pools to the [Aggregate].
+pub const PAUSE_ADD_STAKE_POOL: u8 = 1 << 2;
+/// All pause flags of an [Aggregate].
+pub const AGGREGATE_PAUSE_FLAGS: u8 = PAUSE_MINT | PAUSE_REDEEM | PAUSE_ADD_STAKE_POOL;
+/// All pause flags of a [StakePool].
+pub const STAKE_POOL_PAUSE_FLAGS: u8 = PAUSE_MINT | PAUSE_REDEEM;
+
 /// Contains the info of the aggregate token.
 /// Make sure to allocate enough storage to handle a lot of stake pools.
+///
+/// Fields added since the initial release are appended after [Aggregate::latest_snapshot_ts].
+/// Aggregates created by the initial release cannot be loaded by this version, see the crate docs.
 #[account]
 #[derive(Debug, Default, PartialEq, Eq)]
 pub struct Aggregate {
@@ -24,18 +39,116 @@ pub struct Aggregate {
     pub latest_snapshot: Snapshot,
     /// When the latest snapshot was taken.
     pub latest_snapshot_ts: i64,
+
+    /// Account proposed to become the [Aggregate::curator], or [Pubkey::default] if none.
+    pub pending_curator: Pubkey,
+    /// Account proposed to become the [Aggregate::curator_setter], or [Pubkey::default] if none.
+    pub pending_curator_setter: Pubkey,
+    /// Account that can pause and unpause the aggregate and its stake pools.
+    pub guardian: Pubkey,
+    /// Bitfield of paused operations, e.g. [PAUSE_MINT].
+    pub paused: u8,
+
+    /// aSOL [anchor_spl::token::TokenAccount] receiving the author share of the [crate_token::CrateToken] issue fee.
+    pub author_fee_destination: Pubkey,
+    /// aSOL [anchor_spl::token::TokenAccount] receiving the protocol share of the [crate_token::CrateToken] issue fee.
+    pub protocol_fee_destination: Pubkey,
+    /// Share of the author issue fee paid to the [Referrer] of a deposit, in bps.
+    pub referral_fee_bps: u16,
+
+    /// Share of the staking yield charged as a performance fee, in bps.
+    pub performance_fee_bps: u16,
+    /// aSOL [anchor_spl::token::TokenAccount] receiving the performance fee.
+    pub treasury: Pubkey,
+    /// The highest [Snapshot::asol_price] the performance fee has been charged up to.
+    pub high_water_mark: SOL,
+    /// Performance fee accrued on sync but not yet minted to the [Aggregate::treasury].
+    /// This is counted in the supply of every [Snapshot], so it is owed to the treasury from the sync that accrued it.
+    /// [crate::asol::claim_performance_fee] mints it in full, without the [crate_token::CrateToken] issue fee.
+    pub pending_performance_fee: ASOL,
+
+    /// Fee charged on [crate::asol::withdraw_single], in bps.
+    /// This is kept in the reserves, and is charged on top of the [crate_token::CrateToken] withdraw fee.
+    pub single_withdraw_fee_bps: u16,
 }
 
+/// A stake pool in the [Aggregate].
+/// Fields added since the initial release are appended after [StakePoolMeta::accounting_method].
 #[derive(AnchorSerialize, AnchorDeserialize, Copy, Clone, Debug, Default, PartialEq, Eq)]
 pub struct StakePoolMeta {
     /// Mint of the stake pool.
     pub mint: Pubkey,
     /// The accounting method.
     pub accounting_method: AccountingMethod,
+    /// The account holding the state of the stake pool, e.g. the Marinade state or the SPL stake pool.
+    pub pool_state: Pubkey,
+    /// If true, the stake pool no longer accepts deposits.
+    /// Retired stake pools are still counted in snapshots and may still be redeemed from.
+    pub is_retired: bool,
+    /// Amount of stake pool tokens deposited into the Crate through this program, net of withdrawals.
+    /// Tokens sent directly to the Crate's reserves are not counted, so donations cannot move the price of aSOL.
+    pub reserves: u64,
+    /// Copy of the [StakePool::max_weight_bps], so SOL deposits can be routed without loading every [StakePool].
+    pub max_weight_bps: u16,
+    /// Program owning the [StakePoolMeta::pool_state], e.g. the deployment of the SPL stake pool program running the pool.
+    pub pool_program: Pubkey,
+}
+
+impl Aggregate {
+    /// Whether any of the given operations are paused.
+    pub fn is_paused(&self, flags: u8) -> bool {
+        self.paused & flags != 0
+    }
+
+    /// Whether the issue fee destinations have been set.
+    pub fn has_fee_destinations(&self) -> bool {
+        self.author_fee_destination != Pubkey::default()
+            && self.protocol_fee_destination != Pubkey::default()
+    }
+
+    /// Finds the [StakePoolMeta] of a stake pool mint.
+    pub fn find_stake_pool(&self, mint: Pubkey) -> Option<&StakePoolMeta> {
+        self.stake_pools.iter().find(|pool| pool.mint == mint)
+    }
+
+    /// Finds the [StakePoolMeta] of a stake pool mint, mutably.
+    pub fn find_stake_pool_mut(&mut self, mint: Pubkey) -> Option<&mut StakePoolMeta> {
+        self.stake_pools.iter_mut().find(|pool| pool.mint == mint)
+    }
+}
+
+impl StakePoolMeta {
+    /// Whether SOL can be deposited into the stake pool via [crate::asol::deposit_sol].
+    pub fn accepts_sol_deposits(&self) -> bool {
+        !self.is_retired
+            && matches!(
+                self.accounting_method,
+                AccountingMethod::Marinade | AccountingMethod::Lido
+            )
+    }
+
+    /// The amount of stake pool tokens to account for, given the actual balance of the Crate's reserves.
+    pub fn accounted_balance(&self, reserves_amount: u64) -> u64 {
+        self.reserves.min(reserves_amount)
+    }
+
+    /// Records stake pool tokens deposited into the Crate.
+    pub fn record_deposit(&mut self, amount: u64) -> ProgramResult {
+        self.reserves = unwrap_int!(self.reserves.checked_add(amount));
+        Ok(())
+    }
+
+    /// Records stake pool tokens withdrawn from the Crate.
+    pub fn record_withdrawal(&mut self, amount: u64) -> ProgramResult {
+        self.reserves = unwrap_int!(self.reserves.checked_sub(amount));
+        Ok(())
+    }
 }
 
 /// Contains the state of the [StakePoolMeta].
 /// Currently this is just used for TVL tracking.
+///
+/// Fields added since the initial release are appended after [StakePool::latest_snapshot].
 #[account]
 #[derive(Debug, Default, PartialEq, Eq)]
 pub struct StakePool {
@@ -54,6 +167,55 @@ pub struct StakePool {
 
     /// The latest snapshot of the [StakePool].
     pub latest_snapshot: StakePoolStateSnapshot,
+
+    /// Maximum share of the [Aggregate]'s SOL balance this pool may make up after a deposit, in bps.
+    pub max_weight_bps: u16,
+    /// Maximum SOL value of the [StakePool] tokens the Crate may hold after a deposit.
+    pub deposit_cap: SOL,
+
+    /// Minimum plausible [StakePoolSnapshot::sol_for_1e9] of the stake pool.
+    pub min_sol_for_1e9: SOL,
+    /// Maximum plausible [StakePoolSnapshot::sol_for_1e9] of the stake pool.
+    pub max_sol_for_1e9: SOL,
+
+    /// Bitfield of paused operations on this stake pool, e.g. [PAUSE_MINT].
+    pub paused: u8,
+
+    /// Total amount of [StakePool::mint] tokens ever withdrawn via redemptions, before fees.
+    pub total_amount_withdrawn: u64,
+}
+
+impl StakePool {
+    /// Whether any of the given operations are paused.
+    pub fn is_paused(&self, flags: u8) -> bool {
+        self.paused & flags != 0
+    }
+
+    /// Records `deposit_amount` stake pool tokens deposited for `mint_amount` aSOL in the stats.
+    pub fn record_mint(&mut self, deposit_amount: u64, mint_amount: ASOL) -> ProgramResult {
+        self.stats.total_amount_deposited = unwrap_int!(self
+            .stats
+            .total_amount_deposited
+            .checked_add(deposit_amount));
+        self.stats.total_amount_minted =
+            unwrap_int!(self.stats.total_amount_minted.checked_add(mint_amount));
+        Ok(())
+    }
+
+    /// Records the latest snapshot of the stake pool.
+    pub fn record_snapshot(
+        &mut self,
+        snapshot: &Snapshot,
+        pool_snapshot: &StakePoolSnapshot,
+        now: i64,
+    ) {
+        self.latest_snapshot = StakePoolStateSnapshot {
+            aggregate_balance_sol: snapshot.balance_sol,
+            aggregate_supply: snapshot.supply,
+            snapshot: *pool_snapshot,
+            snapshot_ts: now,
+        };
+    }
 }
 
 /// A balance snapshot of a stake pool.
@@ -78,20 +240,163 @@ pub struct StakePoolStateSnapshot {
     pub snapshot_ts: i64,
 }
 
+/// A history of [Aggregate] snapshots, stored as a ring buffer.
+/// Used to compute a time-weighted aSOL price, see [SnapshotHistory::twap].
+#[account]
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct SnapshotHistory {
+    /// The [Aggregate].
+    pub aggregate: Pubkey,
+    /// The bump.
+    pub bump: u8,
+    /// Index of the entry to overwrite once the buffer is full.
+    pub head: u16,
+    /// Entries, in insertion order starting from [SnapshotHistory::head] once the buffer is full.
+    pub entries: Vec<SnapshotHistoryEntry>,
+}
+
+/// An entry of the [SnapshotHistory].
+#[derive(AnchorSerialize, AnchorDeserialize, Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SnapshotHistoryEntry {
+    /// When the snapshot was taken.
+    pub timestamp: i64,
+    /// Slot the snapshot was taken in.
+    pub slot: u64,
+    /// SOL value of the [Aggregate]'s balance.
+    pub balance_sol: SOL,
+    /// Total supply of aSOL.
+    pub supply: ASOL,
+}
+
+/// The deposits and redemptions of an owner in an [Aggregate].
+/// If passed to a mint or redeem instruction, it is updated with the owner's activity.
+#[account]
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Position {
+    /// The [Aggregate].
+    pub aggregate: Pubkey,
+    /// The owner, i.e. the depositor or redeemer.
+    pub owner: Pubkey,
+    /// The bump.
+    pub bump: u8,
+
+    /// Total [SOL] value of the stake pool tokens deposited, at the time of each deposit.
+    pub total_deposited_sol: SOL,
+    /// Total aSOL received for deposits, net of the issue fee.
+    pub total_minted: ASOL,
+    /// Total aSOL redeemed.
+    pub total_redeemed: ASOL,
+    /// Total [SOL] value of the aSOL redeemed, at the time of each redemption.
+    pub total_redeemed_sol: SOL,
+
+    /// When the first deposit was made.
+    pub first_deposit_ts: i64,
+    /// When the latest deposit was made.
+    pub last_deposit_ts: i64,
+}
+
+impl Position {
+    /// Records a deposit worth `sol` for which `asol` was received.
+    pub fn record_deposit(&mut self, sol: SOL, asol: ASOL, now: i64) -> ProgramResult {
+        self.total_deposited_sol = unwrap_int!(self.total_deposited_sol.checked_add(sol));
+        self.total_minted = unwrap_int!(self.total_minted.checked_add(asol));
+        if self.first_deposit_ts == 0 {
+            self.first_deposit_ts = now;
+        }
+        self.last_deposit_ts = now;
+        Ok(())
+    }
+
+    /// Records a redemption of `asol` worth `sol`.
+    pub fn record_redemption(&mut self, asol: ASOL, sol: SOL) -> ProgramResult {
+        self.total_redeemed = unwrap_int!(self.total_redeemed.checked_add(asol));
+        self.total_redeemed_sol = unwrap_int!(self.total_redeemed_sol.checked_add(sol));
+        Ok(())
+    }
+}
+
+/// A referrer of deposits into an [Aggregate], e.g. a partner frontend.
+#[account]
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Referrer {
+    /// The [Aggregate].
+    pub aggregate: Pubkey,
+    /// The owner of the referrer.
+    pub owner: Pubkey,
+    /// The bump.
+    pub bump: u8,
+    /// aSOL [anchor_spl::token::TokenAccount] receiving the referral fees.
+    pub fee_destination: Pubkey,
+
+    /// Total [SOL] value of the referred deposits.
+    pub total_volume_sol: SOL,
+    /// Total aSOL minted for the referred deposits, including the issue fee.
+    pub total_minted: ASOL,
+    /// Total aSOL paid as referral fees.
+    pub total_fees: ASOL,
+}
+
+impl Referrer {
+    /// Records a referred deposit worth `volume_sol`.
+    pub fn record_referral(&mut self, volume_sol: SOL, minted: ASOL, fee: ASOL) -> ProgramResult {
+        self.total_volume_sol = unwrap_int!(self.total_volume_sol.checked_add(volume_sol));
+        self.total_minted = unwrap_int!(self.total_minted.checked_add(minted));
+        self.total_fees = unwrap_int!(self.total_fees.checked_add(fee));
+        Ok(())
+    }
+}
+
+/// The exchange rate of an [Aggregate], written by [crate::asol::get_exchange_rate].
+/// Other programs may CPI into [crate::asol::get_exchange_rate] and then read this account.
+#[account]
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ExchangeRateOracle {
+    /// The [Aggregate].
+    pub aggregate: Pubkey,
+    /// The bump.
+    pub bump: u8,
+
+    /// [SOL] value of 1e9 aSOL.
+    pub asol_price: SOL,
+    /// The snapshot the price was computed from, including any performance fee that would accrue.
+    pub snapshot: Snapshot,
+    /// Breakdown of the snapshot per stake pool, in the order of [Aggregate::stake_pools].
+    pub stake_pools: Vec<PoolExchangeRate>,
+
+    /// When the exchange rate was computed.
+    pub timestamp: i64,
+    /// Slot the exchange rate was computed in.
+    pub slot: u64,
+    /// Seconds between [Aggregate::latest_snapshot_ts] and [ExchangeRateOracle::timestamp].
+    pub snapshot_age: i64,
+    /// True if the exchange rate of every stake pool was up to date.
+    pub is_current: bool,
+}
+
+/// The exchange rate of a single stake pool in an [ExchangeRateOracle].
+#[derive(AnchorSerialize, AnchorDeserialize, Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct PoolExchangeRate {
+    /// Mint of the stake pool.
+    pub pool_mint: Pubkey,
+    /// [SOL] value of the stake pool tokens held by the aggregate.
+    pub balance_sol: SOL,
+    /// Share of [Snapshot::balance_sol], in bps.
+    pub weight_bps: u16,
+    /// True if the exchange rate of the stake pool was up to date.
+    pub is_current: bool,
+}
+
 /// The accounting method of the stake pool.
 #[repr(C)]
 #[derive(
-    AnchorSerialize, AnchorDeserialize, Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord,
+    AnchorSerialize, AnchorDeserialize, Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord,
 )]
 pub enum AccountingMethod {
     /// Marinade mSOL.
+    #[default]
     Marinade,
     /// Lido stSOL.
     Lido,
-}
-
-impl Default for AccountingMethod {
-    fn default() -> Self {
-        AccountingMethod::Marinade
-    }
+    /// Any pool built on the SPL stake pool program.
+    SplStakePool,
 }
@@ -1,3 +1,8 @@
+//! Fixed-point amounts of [SOL] and [ASOL].
+//!
+//! Every operation that divides takes a [Rounding] direction, so that each call site
+//! states explicitly which way the remainder goes. Rounding should always favour the pool.
+
 use anchor_lang::prelude::*;
 use num_traits::ToPrimitive;
 
@@ -17,50 +22,314 @@ pub struct ASOL {
     pub amount: u64,
 }
 
-impl SOL {
-    /// Converts to u128.
-    pub fn to_u128(&self) -> u128 {
-        self.amount as u128
+/// Direction to round the result of a division in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rounding {
+    /// Round towards zero.
+    Down,
+    /// Round away from zero.
+    Up,
+}
+
+/// Computes `value * numerator / denominator` without intermediate overflow.
+/// Returns [None] if the denominator is zero or the result does not fit in a [u64].
+pub fn mul_div(value: u64, numerator: u64, denominator: u64, rounding: Rounding) -> Option<u64> {
+    let product = (value as u128).checked_mul(numerator.into())?;
+    let denominator: u128 = denominator.into();
+    let quotient = product.checked_div(denominator)?;
+    let result = match rounding {
+        Rounding::Down => quotient,
+        Rounding::Up if product % denominator != 0 => quotient.checked_add(1)?,
+        Rounding::Up => quotient,
+    };
+    result.to_u64()
+}
+
+macro_rules! impl_amount {
+    ($ty:ident) => {
+        impl $ty {
+            /// Converts to u128.
+            pub fn to_u128(&self) -> u128 {
+                self.amount as u128
+            }
+
+            /// Adds two amounts, returning [None] on overflow.
+            pub fn checked_add(&self, other: $ty) -> Option<$ty> {
+                Some($ty::from(self.amount.checked_add(other.amount)?))
+            }
+
+            /// Subtracts two amounts, returning [None] on underflow.
+            pub fn checked_sub(&self, other: $ty) -> Option<$ty> {
+                Some($ty::from(self.amount.checked_sub(other.amount)?))
+            }
+
+            /// Scales the amount by `numerator / denominator`.
+            pub fn checked_mul_div(
+                &self,
+                numerator: u64,
+                denominator: u64,
+                rounding: Rounding,
+            ) -> Option<$ty> {
+                Some($ty::from(mul_div(
+                    self.amount,
+                    numerator,
+                    denominator,
+                    rounding,
+                )?))
+            }
+        }
+
+        impl From<$ty> for u128 {
+            fn from(value: $ty) -> Self {
+                value.to_u128()
+            }
+        }
+
+        impl From<u64> for $ty {
+            fn from(amount: u64) -> Self {
+                $ty { amount }
+            }
+        }
+    };
+}
+
+impl_amount!(SOL);
+impl_amount!(ASOL);
+
+/// The exchange rate between [SOL] and [ASOL], expressed as the [SOL] backing an amount of [ASOL].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ExchangeRate {
+    /// [SOL] backing [ExchangeRate::asol].
+    pub sol: SOL,
+    /// Amount of [ASOL].
+    pub asol: ASOL,
+}
+
+impl ExchangeRate {
+    /// 1 [SOL] per [ASOL].
+    pub const ONE: ExchangeRate = ExchangeRate {
+        sol: SOL { amount: 1 },
+        asol: ASOL { amount: 1 },
+    };
+
+    /// Creates an exchange rate of `sol` per `asol`.
+    pub fn new(sol: SOL, asol: ASOL) -> Self {
+        ExchangeRate { sol, asol }
     }
 
-    /// Converts the [SOL] amount to [ASOL].
-    pub fn checked_mul_asol(&self, numerator: ASOL, denominator: SOL) -> Option<ASOL> {
-        Some(ASOL::from(
-            (self.to_u128())
-                .checked_mul(numerator.to_u128())
-                .and_then(|v| v.checked_div(denominator.to_u128()))
-                .and_then(|v| v.to_u64())?,
-        ))
+    /// Converts [SOL] to [ASOL] at this rate.
+    pub fn sol_to_asol(&self, sol: SOL, rounding: Rounding) -> Option<ASOL> {
+        Some(ASOL::from(mul_div(
+            sol.amount,
+            self.asol.amount,
+            self.sol.amount,
+            rounding,
+        )?))
     }
-}
 
-impl ASOL {
-    /// Converts to u128.
-    pub fn to_u128(&self) -> u128 {
-        self.amount as u128
+    /// Converts [ASOL] to [SOL] at this rate.
+    pub fn asol_to_sol(&self, asol: ASOL, rounding: Rounding) -> Option<SOL> {
+        Some(SOL::from(mul_div(
+            asol.amount,
+            self.sol.amount,
+            self.asol.amount,
+            rounding,
+        )?))
     }
 }
 
-impl From<SOL> for u128 {
-    fn from(sol: SOL) -> Self {
-        sol.to_u128()
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Small deterministic xorshift generator, so property tests are reproducible.
+    struct XorShift(u64);
+
+    impl XorShift {
+        fn next(&mut self) -> u64 {
+            let mut x = self.0;
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            self.0 = x;
+            x
+        }
+
+        /// Picks a value with a random bit width, to cover small and large magnitudes.
+        fn next_amount(&mut self) -> u64 {
+            let bits = self.next() % 65;
+            if bits == 64 {
+                self.next()
+            } else {
+                self.next() & ((1u64 << bits) - 1)
+            }
+        }
     }
-}
 
-impl From<ASOL> for u128 {
-    fn from(asol: ASOL) -> Self {
-        asol.to_u128()
+    const PROPERTY_ITERATIONS: usize = 100_000;
+
+    #[test]
+    fn test_mul_div_exhaustive() {
+        for value in 0..=40u64 {
+            for numerator in 0..=40u64 {
+                for denominator in 0..=40u64 {
+                    let down = mul_div(value, numerator, denominator, Rounding::Down);
+                    let up = mul_div(value, numerator, denominator, Rounding::Up);
+                    if denominator == 0 {
+                        assert_eq!(down, None);
+                        assert_eq!(up, None);
+                        continue;
+                    }
+                    let product = value * numerator;
+                    let down = down.unwrap();
+                    let up = up.unwrap();
+                    // down is the largest value with down * denominator <= product
+                    assert!(down * denominator <= product);
+                    assert!((down + 1) * denominator > product);
+                    // up is the smallest value with up * denominator >= product
+                    assert!(up * denominator >= product);
+                    assert!(up == 0 || (up - 1) * denominator < product);
+                }
+            }
+        }
     }
-}
 
-impl From<u64> for SOL {
-    fn from(amount: u64) -> Self {
-        SOL { amount }
+    #[test]
+    fn test_mul_div_overflow() {
+        assert_eq!(
+            mul_div(u64::MAX, u64::MAX, u64::MAX, Rounding::Down),
+            Some(u64::MAX)
+        );
+        assert_eq!(
+            mul_div(u64::MAX, u64::MAX, u64::MAX, Rounding::Up),
+            Some(u64::MAX)
+        );
+        assert_eq!(mul_div(u64::MAX, 2, 1, Rounding::Down), None);
+        // rounding up can push the result out of range
+        assert_eq!(
+            mul_div(u64::MAX - 1, (1 << 63) + 1, 1 << 63, Rounding::Down),
+            Some(u64::MAX)
+        );
+        assert_eq!(
+            mul_div(u64::MAX - 1, (1 << 63) + 1, 1 << 63, Rounding::Up),
+            None
+        );
     }
-}
 
-impl From<u64> for ASOL {
-    fn from(amount: u64) -> Self {
-        ASOL { amount }
+    #[test]
+    fn test_checked_add_sub() {
+        assert_eq!(SOL::from(1).checked_add(SOL::from(2)), Some(SOL::from(3)));
+        assert_eq!(SOL::from(u64::MAX).checked_add(SOL::from(1)), None);
+        assert_eq!(
+            ASOL::from(3).checked_sub(ASOL::from(2)),
+            Some(ASOL::from(1))
+        );
+        assert_eq!(ASOL::from(0).checked_sub(ASOL::from(1)), None);
+    }
+
+    #[test]
+    fn test_checked_mul_div() {
+        assert_eq!(
+            SOL::from(10).checked_mul_div(1, 3, Rounding::Down),
+            Some(SOL::from(3))
+        );
+        assert_eq!(
+            SOL::from(10).checked_mul_div(1, 3, Rounding::Up),
+            Some(SOL::from(4))
+        );
+        assert_eq!(ASOL::from(10).checked_mul_div(1, 0, Rounding::Down), None);
+    }
+
+    #[test]
+    fn test_exchange_rate_one() {
+        let rate = ExchangeRate::ONE;
+        assert_eq!(
+            rate.sol_to_asol(SOL::from(1_234), Rounding::Down),
+            Some(ASOL::from(1_234))
+        );
+        assert_eq!(
+            rate.asol_to_sol(ASOL::from(1_234), Rounding::Up),
+            Some(SOL::from(1_234))
+        );
+    }
+
+    #[test]
+    fn test_exchange_rate_rounding() {
+        // 3 SOL backs 2 aSOL
+        let rate = ExchangeRate::new(SOL::from(3), ASOL::from(2));
+        assert_eq!(
+            rate.sol_to_asol(SOL::from(2), Rounding::Down),
+            Some(ASOL::from(1))
+        );
+        assert_eq!(
+            rate.sol_to_asol(SOL::from(2), Rounding::Up),
+            Some(ASOL::from(2))
+        );
+        assert_eq!(
+            rate.asol_to_sol(ASOL::from(1), Rounding::Down),
+            Some(SOL::from(1))
+        );
+        assert_eq!(
+            rate.asol_to_sol(ASOL::from(1), Rounding::Up),
+            Some(SOL::from(2))
+        );
+    }
+
+    #[test]
+    fn test_exchange_rate_empty() {
+        let rate = ExchangeRate::new(SOL::from(0), ASOL::from(0));
+        assert_eq!(rate.sol_to_asol(SOL::from(1), Rounding::Down), None);
+        assert_eq!(rate.asol_to_sol(ASOL::from(1), Rounding::Down), None);
+    }
+
+    #[test]
+    fn test_mul_div_properties() {
+        let rng = &mut XorShift(0x2545_f491_4f6c_dd1d);
+        for _ in 0..PROPERTY_ITERATIONS {
+            let value = rng.next_amount();
+            let numerator = rng.next_amount();
+            let denominator = rng.next_amount().max(1);
+            let exact = (value as u128) * (numerator as u128);
+            let down = mul_div(value, numerator, denominator, Rounding::Down);
+            let up = mul_div(value, numerator, denominator, Rounding::Up);
+            match (down, up) {
+                (Some(down), Some(up)) => {
+                    // down <= exact / denominator <= up, and they differ by at most 1
+                    assert!((down as u128) * (denominator as u128) <= exact);
+                    assert!((up as u128) * (denominator as u128) >= exact);
+                    assert!(up - down <= 1);
+                    assert_eq!(up == down, (down as u128) * (denominator as u128) == exact);
+                }
+                (Some(down), None) => assert_eq!(down, u64::MAX),
+                (None, None) => assert!(exact / (denominator as u128) > u64::MAX as u128),
+                (None, Some(_)) => panic!("rounding up cannot succeed when rounding down fails"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_exchange_rate_round_trip_favours_pool() {
+        let rng = &mut XorShift(0x9e37_79b9_7f4a_7c15);
+        for _ in 0..PROPERTY_ITERATIONS {
+            let rate = ExchangeRate::new(
+                SOL::from(rng.next_amount().max(1)),
+                ASOL::from(rng.next_amount().max(1)),
+            );
+            let sol = SOL::from(rng.next_amount());
+
+            // depositing then redeeming never returns more SOL than was put in
+            if let Some(asol) = rate.sol_to_asol(sol, Rounding::Down) {
+                if let Some(redeemed) = rate.asol_to_sol(asol, Rounding::Down) {
+                    assert!(redeemed <= sol);
+                }
+            }
+
+            // the aSOL required to withdraw an amount of SOL is never less than its value
+            if let Some(asol) = rate.sol_to_asol(sol, Rounding::Up) {
+                if let Some(withdrawn) = rate.asol_to_sol(asol, Rounding::Up) {
+                    assert!(withdrawn >= sol);
+                }
+            }
+        }
     }
 }
@@ -0,0 +1,23 @@
+#!/usr/bin/env sh
+
+cd $(dirname $0)/..
+
+mkdir -p artifacts/programs/ artifacts/accounts/
+
+solana program dump MarBmsSgKXdrN1egZf5sqe1TMai9K1rChYNDJgjq7aD \
+    artifacts/programs/marinade.so --url mainnet-beta
+
+solana program dump CrX7kMhLC3cSsXJdT7JDgqrRVWGnUpX3gfEfxxU2NVLi \
+    artifacts/programs/lido.so --url mainnet-beta
+
+# Marinade state, mSOL mint, mSOL leg of the liquidity pool,
+# Solido account and stSOL mint
+for account in \
+    8szGkuLTAux9XMgZ2vtY39jVSowEcpBfFfD8hXSEqdGC \
+    mSoLzYCxHdYgdzU16g5QSh3i5K3z3KZK7ytfqcJm7So \
+    7GgPYjS5Dza89wV6FpZ23kUJRG5vbQ1GM25ezspYFSoE \
+    49Yi1TKkNyYjPAFdR9LBvoHcUjuPX4Df5T5yv39w2XTn \
+    7dHbWXmci3dT8UFYWYZweBLXgycu7Y3iL6trKn1Y7ARj; do
+    solana account $account --output json \
+        --output-file artifacts/accounts/$account.json --url mainnet-beta
+done
@@ -0,0 +1,28 @@
+#!/usr/bin/env sh
+
+# Runs the e2e tests against the Marinade and Lido fixtures pulled by ./scripts/pull-stake-pools.sh.
+# Anchor.toml can only load programs, so the validator is started here.
+
+cd $(dirname $0)/..
+
+ACCOUNTS=""
+for account in artifacts/accounts/*.json; do
+    ACCOUNTS="$ACCOUNTS --account $(basename $account .json) $account"
+done
+
+solana-test-validator --reset --quiet --ledger .anchor/test-ledger \
+    --mint $(solana-keygen pubkey tests/fixture-key.json) \
+    --bpf-program AURUqAcTZP8mhR6sWVxWyfBbpJRj4A3qqeFzLNhrwayE target/deploy/asol.so \
+    --bpf-program CRATwLpu6YZEeiVq9ajjxs61wPQ9f29s1UoQR9siJCRs artifacts/programs/crate_token.so \
+    --bpf-program 1NKyU3qShZC3oJgvCCftAHDi5TFxcJwfyUz2FeZsiwE artifacts/programs/crate_redeem_in_kind.so \
+    --bpf-program MarBmsSgKXdrN1egZf5sqe1TMai9K1rChYNDJgjq7aD artifacts/programs/marinade.so \
+    --bpf-program CrX7kMhLC3cSsXJdT7JDgqrRVWGnUpX3gfEfxxU2NVLi artifacts/programs/lido.so \
+    $ACCOUNTS &
+VALIDATOR_PID=$!
+trap "kill $VALIDATOR_PID" EXIT
+
+until solana cluster-version --url localhost >/dev/null 2>&1; do
+    sleep 1
+done
+
+anchor test --skip-build --skip-local-validator tests/*.ts
@@ -1,6 +1,5 @@
 import {
   CRATE_ADDRESSES,
-  CRATE_REDEEM_IN_KIND_WITHDRAW_AUTHORITY,
   CrateSDK,
   generateCrateAddress,
 } from "@crateprotocol/crate-sdk";
@@ -14,28 +13,51 @@ import {
 import type { TokenAmount } from "@saberhq/token-utils";
 import {
   createInitMintInstructions,
-  getATAAddresses,
+  getATAAddress,
+  getOrCreateATA,
   getOrCreateATAs,
   TOKEN_PROGRAM_ID,
+  u64,
 } from "@saberhq/token-utils";
-import type { PublicKey, Signer } from "@solana/web3.js";
-import { Keypair, SystemProgram } from "@solana/web3.js";
+import type {
+  AccountMeta,
+  PublicKey,
+  Signer,
+  TransactionInstruction,
+} from "@solana/web3.js";
+import {
+  Keypair,
+  StakeProgram,
+  SystemProgram,
+  SYSVAR_CLOCK_PUBKEY,
+  SYSVAR_RENT_PUBKEY,
+  SYSVAR_STAKE_HISTORY_PUBKEY,
+} from "@solana/web3.js";
 
 import { generateStakePoolAddress } from ".";
 import {
   ASOL_PROGRAM_ID,
+  CRATE_FEE_TO_ADDRESS,
   LAMPORTS_DECIMALS,
-  LIDO_STAKED_SOL,
-  MARINADE_STAKED_SOL,
-  MARINADE_STATE_ACCOUNT,
-  SOLIDO_ACCOUNT,
-  STAKE_POOL_TOKENS,
+  LIDO_PROGRAM_ID,
+  MARINADE_LIQ_POOL_MSOL_LEG,
+  MARINADE_PROGRAM_ID,
 } from "./constants";
-import { generateAggregateAddress } from "./pda";
+import {
+  generateAggregateAddress,
+  generateExchangeRateOracleAddress,
+  generateLidoAddress,
+  generateMarinadeAddress,
+  generatePositionAddress,
+  generateReferrerAddress,
+  generateSnapshotHistoryAddress,
+} from "./pda";
 import type {
   AccountingMethod,
   AggregateData,
   ASolProgram,
+  ReferrerData,
+  StakePoolMeta,
 } from "./programs/asol";
 import { AsolJSON } from "./programs/asol";
 
@@ -108,6 +130,10 @@ export class ASolSDK {
   }> {
     const [crateKey, crateBump] = await generateCrateAddress(mintKP.publicKey);
     const [aggregateKey, aggBump] = await generateAggregateAddress(crateKey);
+    const [snapshotHistory, historyBump] = await generateSnapshotHistoryAddress(
+      aggregateKey,
+      this.program.programId
+    );
     const initMintTX = await createInitMintInstructions({
       provider: this.provider,
       mintKP,
@@ -116,12 +142,12 @@ export class ASolSDK {
       freezeAuthority: crateKey,
     });
     const newAggregateTX = new TransactionEnvelope(this.provider, [
-      this.program.instruction.newAggregate(aggBump, crateBump, {
+      this.program.instruction.newAggregate(aggBump, crateBump, historyBump, {
         accounts: {
           crateMint: mintKP.publicKey,
           payer,
-          redeemInKind: CRATE_REDEEM_IN_KIND_WITHDRAW_AUTHORITY,
           aggregate: aggregateKey,
+          snapshotHistory,
           crateToken: crateKey,
           admin,
           systemProgram: SystemProgram.programId,
@@ -129,7 +155,130 @@ export class ASolSDK {
         },
       }),
     ]);
-    return { tx: initMintTX.combine(newAggregateTX), aggregateKey, crateKey };
+    return {
+      tx: initMintTX.combine(newAggregateTX),
+      aggregateKey,
+      crateKey,
+    };
+  }
+
+  /**
+   * Creates the position of an owner in an Aggregate.
+   * @returns
+   */
+  async newPosition({
+    aggregate,
+    owner = this.provider.wallet.publicKey,
+    payer = this.provider.wallet.publicKey,
+  }: {
+    aggregate: PublicKey;
+    owner?: PublicKey;
+    payer?: PublicKey;
+  }): Promise<{ tx: TransactionEnvelope; positionKey: PublicKey }> {
+    const [position, bump] = await generatePositionAddress(
+      aggregate,
+      owner,
+      this.program.programId
+    );
+    const tx = new TransactionEnvelope(this.provider, [
+      this.program.instruction.newPosition(bump, {
+        accounts: {
+          aggregate,
+          position,
+          owner,
+          payer,
+          systemProgram: SystemProgram.programId,
+        },
+      }),
+    ]);
+    return { tx, positionKey: position };
+  }
+
+  /**
+   * Creates a referrer of deposits into an Aggregate.
+   * @returns
+   */
+  async newReferrer({
+    aggregate,
+    feeDestination,
+    owner = this.provider.wallet.publicKey,
+    payer = this.provider.wallet.publicKey,
+  }: {
+    aggregate: PublicKey;
+    /**
+     * aSOL token account receiving the referral fees.
+     */
+    feeDestination: PublicKey;
+    owner?: PublicKey;
+    payer?: PublicKey;
+  }): Promise<{ tx: TransactionEnvelope; referrerKey: PublicKey }> {
+    const aggregateData = (await this.program.account.aggregate.fetchNullable(
+      aggregate
+    )) as AggregateData;
+    if (!aggregateData) {
+      throw new Error("No aggregate found.");
+    }
+    const [referrer, bump] = await generateReferrerAddress(
+      aggregate,
+      owner,
+      this.program.programId
+    );
+    const tx = new TransactionEnvelope(this.provider, [
+      this.program.instruction.newReferrer(bump, {
+        accounts: {
+          aggregate,
+          crateToken: aggregateData.crateToken,
+          referrer,
+          owner,
+          feeDestination,
+          payer,
+          systemProgram: SystemProgram.programId,
+        },
+      }),
+    ]);
+    return { tx, referrerKey: referrer };
+  }
+
+  /**
+   * Gets the remaining accounts of the referrer of a deposit, if any.
+   */
+  async getReferrerAccounts(
+    aggregate: PublicKey,
+    referrerOwner?: PublicKey
+  ): Promise<AccountMeta[]> {
+    if (!referrerOwner) {
+      return [];
+    }
+    const [referrer] = await generateReferrerAddress(aggregate, referrerOwner);
+    const referrerData = (await this.program.account.referrer.fetchNullable(
+      referrer
+    )) as ReferrerData;
+    if (!referrerData) {
+      throw new Error("No referrer found.");
+    }
+    return [
+      { pubkey: referrer, isSigner: false, isWritable: true },
+      {
+        pubkey: referrerData.feeDestination,
+        isSigner: false,
+        isWritable: true,
+      },
+    ];
+  }
+
+  /**
+   * Gets the remaining account of the position of an owner, if it should be updated.
+   */
+  async getPositionAccounts(
+    aggregate: PublicKey,
+    owner: PublicKey,
+    updatePosition: boolean
+  ): Promise<AccountMeta[]> {
+    if (!updatePosition) {
+      return [];
+    }
+    const [position] = await generatePositionAddress(aggregate, owner);
+    return [{ pubkey: position, isSigner: false, isWritable: true }];
   }
 
   /**
@@ -140,12 +289,17 @@ export class ASolSDK {
     aggregate,
     mint,
     method,
+    poolState,
     curator = this.provider.wallet.publicKey,
     payer = this.provider.wallet.publicKey,
   }: {
     aggregate: PublicKey;
     mint: PublicKey;
     method: AccountingMethod;
+    /**
+     * The account holding the state of the stake pool, e.g. the SPL stake pool.
+     */
+    poolState: PublicKey;
     curator?: PublicKey;
     payer?: PublicKey;
   }): Promise<{ tx: TransactionEnvelope; stakePoolKey: PublicKey }> {
@@ -160,6 +314,7 @@ export class ASolSDK {
           aggregate,
           stakePool,
           mint,
+          poolState,
           curator,
           payer,
           systemProgram: SystemProgram.programId,
@@ -170,113 +325,1277 @@ export class ASolSDK {
   }
 
   /**
-   * Mints tokens.
+   * Retires a stake pool, preventing new deposits into it.
    * @returns
    */
-  async mintASol({
-    aggregateKey,
-    amount,
-    depositor = this.provider.wallet.publicKey,
+  async retireStakePool({
+    aggregate,
+    mint,
+    curator = this.provider.wallet.publicKey,
   }: {
-    aggregateKey: PublicKey;
-    amount: TokenAmount;
-    depositor?: PublicKey;
+    aggregate: PublicKey;
+    mint: PublicKey;
+    curator?: PublicKey;
   }): Promise<TransactionEnvelope> {
-    const depositMint = amount.token.mintAccount;
-    const method = depositMint.equals(MARINADE_STAKED_SOL)
-      ? "mintMarinade"
-      : depositMint.equals(LIDO_STAKED_SOL)
-      ? "mintLido"
-      : null;
-    if (!method) {
-      throw new Error("Invalid mint.");
-    }
+    const [stakePool] = await generateStakePoolAddress(
+      aggregate,
+      mint,
+      this.program.programId
+    );
+    return new TransactionEnvelope(this.provider, [
+      this.program.instruction.retireStakePool({
+        accounts: {
+          aggregate,
+          stakePool,
+          curator,
+        },
+      }),
+    ]);
+  }
 
-    const aggregate = (await this.program.account.aggregate.fetchNullable(
-      aggregateKey
+  /**
+   * Sets the issue and withdraw fees of the aggregate's Crate.
+   * @returns
+   */
+  async setFees({
+    aggregate,
+    issueFeeBps,
+    withdrawFeeBps,
+    curator = this.provider.wallet.publicKey,
+  }: {
+    aggregate: PublicKey;
+    issueFeeBps: number;
+    withdrawFeeBps: number;
+    curator?: PublicKey;
+  }): Promise<TransactionEnvelope> {
+    const aggregateData = (await this.program.account.aggregate.fetchNullable(
+      aggregate
     )) as AggregateData;
-    if (!aggregate) {
+    if (!aggregateData) {
       throw new Error("No aggregate found.");
     }
+    return new TransactionEnvelope(this.provider, [
+      this.program.instruction.setFees(issueFeeBps, withdrawFeeBps, {
+        accounts: {
+          aggregate,
+          curator,
+          crateToken: aggregateData.crateToken,
+          crateTokenProgram: CRATE_ADDRESSES.CrateToken,
+        },
+      }),
+    ]);
+  }
 
-    const crate = await this.crate.fetchCrateTokenData(aggregate.crateToken);
+  /**
+   * Sets the aSOL accounts receiving the issue fees to the associated token accounts
+   * of the Crate's author fee recipient and the Crate protocol.
+   * @returns
+   */
+  async setFeeDestinations({
+    aggregate,
+    curator = this.provider.wallet.publicKey,
+  }: {
+    aggregate: PublicKey;
+    curator?: PublicKey;
+  }): Promise<TransactionEnvelope> {
+    const aggregateData = (await this.program.account.aggregate.fetchNullable(
+      aggregate
+    )) as AggregateData;
+    if (!aggregateData) {
+      throw new Error("No aggregate found.");
+    }
+    const crate = await this.crate.fetchCrateTokenData(
+      aggregateData.crateToken
+    );
     if (!crate) {
       throw new Error("No crate found.");
     }
 
-    const stakePoolATAs = await getATAAddresses({
-      mints: {
-        marinade: MARINADE_STAKED_SOL,
-        lido: LIDO_STAKED_SOL,
-        input: amount.token.mintAccount,
-      },
-      owner: aggregate.crateToken,
+    const authorFeeDestination = await getOrCreateATA({
+      provider: this.provider,
+      mint: crate.mint,
+      owner: crate.authorFeeTo,
     });
-
-    const depositorATAs = await getOrCreateATAs({
+    const protocolFeeDestination = await getOrCreateATA({
       provider: this.provider,
-      mints: {
-        input: amount.token.mintAccount,
-        crate: crate.mint,
-      },
-      owner: depositor,
+      mint: crate.mint,
+      owner: CRATE_FEE_TO_ADDRESS,
     });
+    const instructions: TransactionInstruction[] = [];
+    if (authorFeeDestination.instruction) {
+      instructions.push(authorFeeDestination.instruction);
+    }
+    if (protocolFeeDestination.instruction) {
+      instructions.push(protocolFeeDestination.instruction);
+    }
 
-    const [stakePool] = await generateStakePoolAddress(
-      aggregateKey,
-      amount.token.mintAccount
-    );
+    return new TransactionEnvelope(this.provider, [
+      ...instructions,
+      this.program.instruction.setFeeDestinations({
+        accounts: {
+          aggregate,
+          curator,
+          crateToken: aggregateData.crateToken,
+          authorFeeDestination: authorFeeDestination.address,
+          protocolFeeDestination: protocolFeeDestination.address,
+        },
+      }),
+    ]);
+  }
 
-    const mintTX = new TransactionEnvelope(this.provider, [
-      ...(depositorATAs.createAccountInstructions.crate
-        ? [depositorATAs.createAccountInstructions.crate]
-        : []),
-      this.program.instruction[method](amount.toU64(), {
+  /**
+   * Transfers the balance of a fee account owned by the aggregate to a recipient.
+   * Defaults to the author fee account of the aggregate's aSOL.
+   * @returns
+   */
+  async sweepFees({
+    aggregate,
+    mint,
+    recipient = this.provider.wallet.publicKey,
+    curator = this.provider.wallet.publicKey,
+  }: {
+    aggregate: PublicKey;
+    /**
+     * Mint of the fees to sweep. Defaults to aSOL.
+     */
+    mint?: PublicKey;
+    /**
+     * Owner of the account receiving the fees.
+     */
+    recipient?: PublicKey;
+    curator?: PublicKey;
+  }): Promise<TransactionEnvelope> {
+    const aggregateData = (await this.program.account.aggregate.fetchNullable(
+      aggregate
+    )) as AggregateData;
+    if (!aggregateData) {
+      throw new Error("No aggregate found.");
+    }
+    let feeMint = mint;
+    if (!feeMint) {
+      const crate = await this.crate.fetchCrateTokenData(
+        aggregateData.crateToken
+      );
+      if (!crate) {
+        throw new Error("No crate found.");
+      }
+      feeMint = crate.mint;
+    }
+
+    const feeRecipient = await getOrCreateATA({
+      provider: this.provider,
+      mint: feeMint,
+      owner: recipient,
+    });
+    return new TransactionEnvelope(this.provider, [
+      ...(feeRecipient.instruction ? [feeRecipient.instruction] : []),
+      this.program.instruction.sweepFees({
         accounts: {
-          sync: {
-            marinade: {
-              marinade: MARINADE_STATE_ACCOUNT,
-              marinadeStakePoolTokens: stakePoolATAs.accounts.marinade.address,
-            },
-            lido: {
-              lido: SOLIDO_ACCOUNT,
-              lidoStakePoolTokens: stakePoolATAs.accounts.lido.address,
-            },
-          },
-          mintAsol: {
-            aggregate: aggregateKey,
-            stakePool,
-            crateToken: aggregate.crateToken,
-            crateMint: crate.mint,
-            tokenProgram: TOKEN_PROGRAM_ID,
-            crateTokenProgram: CRATE_ADDRESSES.CrateToken,
+          aggregate,
+          curator,
+          feeSource: await getATAAddress({ mint: feeMint, owner: aggregate }),
+          feeRecipient: feeRecipient.address,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        },
+      }),
+    ]);
+  }
 
-            depositor,
-            depositorSource: depositorATAs.accounts.input,
-            stakePoolTokens: stakePoolATAs.accounts.input.address,
-            mintDestination: depositorATAs.accounts.crate,
-          },
+  /**
+   * Sets the share of the author issue fee paid to the referrer of a deposit.
+   * @returns
+   */
+  setReferralFee({
+    aggregate,
+    referralFeeBps,
+    curator = this.provider.wallet.publicKey,
+  }: {
+    aggregate: PublicKey;
+    /**
+     * Share of the author issue fee paid to referrers, in bps.
+     */
+    referralFeeBps: number;
+    curator?: PublicKey;
+  }): TransactionEnvelope {
+    return new TransactionEnvelope(this.provider, [
+      this.program.instruction.setReferralFee(referralFeeBps, {
+        accounts: {
+          aggregate,
+          curator,
         },
       }),
     ]);
+  }
 
-    return mintTX;
+  /**
+   * Sets the performance fee and the aSOL account of the treasury receiving it.
+   * @returns
+   */
+  async setPerformanceFee({
+    aggregate,
+    performanceFeeBps,
+    treasury,
+    curator = this.provider.wallet.publicKey,
+  }: {
+    aggregate: PublicKey;
+    /**
+     * Share of the staking yield charged as a fee, in bps.
+     */
+    performanceFeeBps: number;
+    /**
+     * aSOL token account receiving the fee.
+     */
+    treasury: PublicKey;
+    curator?: PublicKey;
+  }): Promise<TransactionEnvelope> {
+    const aggregateData = (await this.program.account.aggregate.fetchNullable(
+      aggregate
+    )) as AggregateData;
+    if (!aggregateData) {
+      throw new Error("No aggregate found.");
+    }
+    return new TransactionEnvelope(this.provider, [
+      this.program.instruction.setPerformanceFee(performanceFeeBps, {
+        accounts: {
+          aggregate,
+          curator,
+          crateToken: aggregateData.crateToken,
+          treasury,
+        },
+      }),
+    ]);
   }
 
   /**
-   * Redeems Crate tokens for the underlying tokens.
+   * Mints the accrued performance fee to the treasury.
+   * @returns
    */
-  async redeem({
-    amount,
-    owner = this.provider.wallet.publicKey,
+  async claimPerformanceFee({
+    aggregate,
   }: {
-    amount: TokenAmount;
-    owner?: PublicKey;
+    aggregate: PublicKey;
   }): Promise<TransactionEnvelope> {
-    return await this.crate.redeem({
-      amount,
-      owner,
-      underlyingTokens: Object.values(STAKE_POOL_TOKENS),
-    });
+    const aggregateData = (await this.program.account.aggregate.fetchNullable(
+      aggregate
+    )) as AggregateData;
+    if (!aggregateData) {
+      throw new Error("No aggregate found.");
+    }
+    const crate = await this.crate.fetchCrateTokenData(
+      aggregateData.crateToken
+    );
+    if (!crate) {
+      throw new Error("No crate found.");
+    }
+    return new TransactionEnvelope(this.provider, [
+      this.program.instruction.claimPerformanceFee({
+        accounts: {
+          aggregate,
+          crateToken: aggregateData.crateToken,
+          crateMint: crate.mint,
+          treasury: aggregateData.treasury,
+          // fee destinations are only checked if there is an issue fee
+          authorFeeDestination:
+            crate.issueFeeBps === 0
+              ? aggregateData.treasury
+              : aggregateData.authorFeeDestination,
+          protocolFeeDestination:
+            crate.issueFeeBps === 0
+              ? aggregateData.treasury
+              : aggregateData.protocolFeeDestination,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          crateTokenProgram: CRATE_ADDRESSES.CrateToken,
+        },
+      }),
+    ]);
+  }
+
+  /**
+   * Sets the max weight and deposit cap of a stake pool.
+   * @returns
+   */
+  async setStakePoolLimits({
+    aggregate,
+    mint,
+    maxWeightBps,
+    depositCap,
+    curator = this.provider.wallet.publicKey,
+  }: {
+    aggregate: PublicKey;
+    mint: PublicKey;
+    /**
+     * Maximum share of the aggregate the stake pool may make up, in bps.
+     */
+    maxWeightBps: number;
+    /**
+     * Maximum SOL value of the stake pool tokens held by the aggregate, in lamports.
+     */
+    depositCap: u64;
+    curator?: PublicKey;
+  }): Promise<TransactionEnvelope> {
+    const [stakePool] = await generateStakePoolAddress(
+      aggregate,
+      mint,
+      this.program.programId
+    );
+    return new TransactionEnvelope(this.provider, [
+      this.program.instruction.setStakePoolLimits(maxWeightBps, depositCap, {
+        accounts: {
+          aggregate,
+          stakePool,
+          curator,
+        },
+      }),
+    ]);
+  }
+
+  /**
+   * Sets the band of plausible prices of a stake pool.
+   * @returns
+   */
+  async setStakePoolPriceBand({
+    aggregate,
+    mint,
+    minSolFor1e9,
+    maxSolFor1e9,
+    curator = this.provider.wallet.publicKey,
+  }: {
+    aggregate: PublicKey;
+    mint: PublicKey;
+    /**
+     * Minimum SOL value of 1e9 stake pool tokens, in lamports.
+     */
+    minSolFor1e9: u64;
+    /**
+     * Maximum SOL value of 1e9 stake pool tokens, in lamports.
+     */
+    maxSolFor1e9: u64;
+    curator?: PublicKey;
+  }): Promise<TransactionEnvelope> {
+    const [stakePool] = await generateStakePoolAddress(
+      aggregate,
+      mint,
+      this.program.programId
+    );
+    return new TransactionEnvelope(this.provider, [
+      this.program.instruction.setStakePoolPriceBand(
+        minSolFor1e9,
+        maxSolFor1e9,
+        {
+          accounts: {
+            aggregate,
+            stakePool,
+            curator,
+          },
+        }
+      ),
+    ]);
+  }
+
+  /**
+   * Removes a stake pool. All stake pool tokens deposited through the aggregate must have been withdrawn.
+   * Tokens sent directly to the Crate's reserves are withdrawn to the dust recipient.
+   * @returns
+   */
+  async removeStakePool({
+    aggregate,
+    mint,
+    dustRecipient = this.provider.wallet.publicKey,
+    curator = this.provider.wallet.publicKey,
+  }: {
+    aggregate: PublicKey;
+    mint: PublicKey;
+    /**
+     * Owner of the account receiving the untracked stake pool tokens left in the reserves.
+     */
+    dustRecipient?: PublicKey;
+    curator?: PublicKey;
+  }): Promise<TransactionEnvelope> {
+    const aggregateData = (await this.program.account.aggregate.fetchNullable(
+      aggregate
+    )) as AggregateData;
+    if (!aggregateData) {
+      throw new Error("No aggregate found.");
+    }
+    const [stakePool] = await generateStakePoolAddress(
+      aggregate,
+      mint,
+      this.program.programId
+    );
+    const dustDestination = await getOrCreateATA({
+      provider: this.provider,
+      mint,
+      owner: dustRecipient,
+    });
+    return new TransactionEnvelope(this.provider, [
+      ...(dustDestination.instruction ? [dustDestination.instruction] : []),
+      this.program.instruction.removeStakePool({
+        accounts: {
+          aggregate,
+          stakePool,
+          crateToken: aggregateData.crateToken,
+          crateReserves: await getATAAddress({
+            mint,
+            owner: aggregateData.crateToken,
+          }),
+          dustDestination: dustDestination.address,
+          authorFeeDestination: await getATAAddress({
+            mint,
+            owner: aggregate,
+          }),
+          protocolFeeDestination: await getATAAddress({
+            mint,
+            owner: CRATE_FEE_TO_ADDRESS,
+          }),
+          curator,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          crateTokenProgram: CRATE_ADDRESSES.CrateToken,
+        },
+      }),
+    ]);
+  }
+
+  /**
+   * Mints tokens.
+   * @returns
+   */
+  async mintASol({
+    aggregateKey,
+    amount,
+    minMintAmount = new u64(0),
+    depositor = this.provider.wallet.publicKey,
+    referrer,
+    updatePosition = false,
+  }: {
+    aggregateKey: PublicKey;
+    amount: TokenAmount;
+    /**
+     * Minimum amount of aSOL to receive. The transaction fails if less would be minted.
+     */
+    minMintAmount?: u64;
+    depositor?: PublicKey;
+    /**
+     * Owner of the referrer of the deposit, if any.
+     */
+    referrer?: PublicKey;
+    /**
+     * If true, records the deposit on the depositor's position, which must already exist.
+     */
+    updatePosition?: boolean;
+  }): Promise<TransactionEnvelope> {
+    const aggregate = (await this.program.account.aggregate.fetchNullable(
+      aggregateKey
+    )) as AggregateData;
+    if (!aggregate) {
+      throw new Error("No aggregate found.");
+    }
+
+    const depositMint = amount.token.mintAccount;
+    const pool = aggregate.stakePools.find((pool) =>
+      pool.mint.equals(depositMint)
+    );
+    const method = !pool
+      ? null
+      : "marinade" in pool.accountingMethod
+      ? "mintMarinade"
+      : "lido" in pool.accountingMethod
+      ? "mintLido"
+      : "splStakePool" in pool.accountingMethod
+      ? "mintSplStakePool"
+      : null;
+    if (!method) {
+      throw new Error("Invalid mint.");
+    }
+
+    const { accounts, createAccountInstructions } =
+      await this.getMintASolAccounts({
+        aggregateKey,
+        aggregate,
+        mint: depositMint,
+        depositor,
+      });
+
+    const mintTX = new TransactionEnvelope(this.provider, [
+      ...(createAccountInstructions.crate
+        ? [createAccountInstructions.crate]
+        : []),
+      this.program.instruction[method](
+        amount.toU64(),
+        minMintAmount,
+        { referrer: !!referrer, position: updatePosition },
+        {
+          accounts: { mintAsol: accounts },
+          remainingAccounts: [
+            ...(await this.getSyncAccounts(aggregate)),
+            ...(await this.getReferrerAccounts(aggregateKey, referrer)),
+            ...(await this.getPositionAccounts(
+              aggregateKey,
+              depositor,
+              updatePosition
+            )),
+          ],
+        }
+      ),
+    ]);
+
+    return mintTX;
+  }
+
+  /**
+   * Gets the accounts to mint aSOL for the tokens of a stake pool, shared by all deposit instructions.
+   */
+  async getMintASolAccounts({
+    aggregateKey,
+    aggregate,
+    mint,
+    depositor,
+  }: {
+    aggregateKey: PublicKey;
+    aggregate: AggregateData;
+    /**
+     * Mint of the stake pool tokens being deposited.
+     */
+    mint: PublicKey;
+    depositor: PublicKey;
+  }): Promise<{
+    accounts: {
+      aggregate: PublicKey;
+      snapshotHistory: PublicKey;
+      stakePool: PublicKey;
+      crateToken: PublicKey;
+      crateMint: PublicKey;
+      tokenProgram: PublicKey;
+      crateTokenProgram: PublicKey;
+      depositor: PublicKey;
+      depositorSource: PublicKey;
+      stakePoolTokens: PublicKey;
+      mintDestination: PublicKey;
+      authorFeeDestination: PublicKey;
+      protocolFeeDestination: PublicKey;
+    };
+    createAccountInstructions: {
+      input?: TransactionInstruction;
+      crate?: TransactionInstruction;
+    };
+  }> {
+    const crate = await this.crate.fetchCrateTokenData(aggregate.crateToken);
+    if (!crate) {
+      throw new Error("No crate found.");
+    }
+
+    const depositorATAs = await getOrCreateATAs({
+      provider: this.provider,
+      mints: {
+        input: mint,
+        crate: crate.mint,
+      },
+      owner: depositor,
+    });
+
+    const [stakePool] = await generateStakePoolAddress(aggregateKey, mint);
+
+    return {
+      accounts: {
+        aggregate: aggregateKey,
+        snapshotHistory: (
+          await generateSnapshotHistoryAddress(aggregateKey)
+        )[0],
+        stakePool,
+        crateToken: aggregate.crateToken,
+        crateMint: crate.mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        crateTokenProgram: CRATE_ADDRESSES.CrateToken,
+
+        depositor,
+        depositorSource: depositorATAs.accounts.input,
+        stakePoolTokens: await getATAAddress({
+          mint,
+          owner: aggregate.crateToken,
+        }),
+        mintDestination: depositorATAs.accounts.crate,
+        // fee destinations are only checked if there is an issue fee
+        authorFeeDestination:
+          crate.issueFeeBps === 0
+            ? depositorATAs.accounts.crate
+            : aggregate.authorFeeDestination,
+        protocolFeeDestination:
+          crate.issueFeeBps === 0
+            ? depositorATAs.accounts.crate
+            : aggregate.protocolFeeDestination,
+      },
+      createAccountInstructions: depositorATAs.createAccountInstructions,
+    };
+  }
+
+  /**
+   * Mints aSOL from the tokens of several stake pools in one instruction.
+   * @returns
+   */
+  async mintMulti({
+    aggregateKey,
+    amounts,
+    minMintAmount = new u64(0),
+    depositor = this.provider.wallet.publicKey,
+    referrer,
+    updatePosition = false,
+  }: {
+    aggregateKey: PublicKey;
+    /**
+     * Amounts of stake pool tokens to deposit, at most one per stake pool.
+     */
+    amounts: TokenAmount[];
+    /**
+     * Minimum total amount of aSOL to receive. The transaction fails if less would be minted.
+     */
+    minMintAmount?: u64;
+    depositor?: PublicKey;
+    /**
+     * Owner of the referrer of the deposits, if any.
+     */
+    referrer?: PublicKey;
+    /**
+     * If true, records the deposits on the depositor's position, which must already exist.
+     */
+    updatePosition?: boolean;
+  }): Promise<TransactionEnvelope> {
+    const aggregate = (await this.program.account.aggregate.fetchNullable(
+      aggregateKey
+    )) as AggregateData;
+    if (!aggregate) {
+      throw new Error("No aggregate found.");
+    }
+    const crate = await this.crate.fetchCrateTokenData(aggregate.crateToken);
+    if (!crate) {
+      throw new Error("No crate found.");
+    }
+
+    const mintDestination = await getOrCreateATA({
+      provider: this.provider,
+      mint: crate.mint,
+      owner: depositor,
+    });
+
+    const poolAccounts: AccountMeta[] = [];
+    for (const amount of amounts) {
+      const mint = amount.token.mintAccount;
+      const [stakePool] = await generateStakePoolAddress(aggregateKey, mint);
+      poolAccounts.push(
+        { pubkey: stakePool, isSigner: false, isWritable: true },
+        {
+          pubkey: await getATAAddress({ mint, owner: aggregate.crateToken }),
+          isSigner: false,
+          isWritable: true,
+        },
+        {
+          pubkey: await getATAAddress({ mint, owner: depositor }),
+          isSigner: false,
+          isWritable: true,
+        }
+      );
+    }
+
+    return new TransactionEnvelope(this.provider, [
+      ...(mintDestination.instruction ? [mintDestination.instruction] : []),
+      this.program.instruction.mintMulti(
+        amounts.map((amount) => ({
+          mint: amount.token.mintAccount,
+          amount: amount.toU64(),
+        })),
+        minMintAmount,
+        { referrer: !!referrer, position: updatePosition },
+        {
+          accounts: {
+            aggregate: aggregateKey,
+            snapshotHistory: (
+              await generateSnapshotHistoryAddress(aggregateKey)
+            )[0],
+            crateToken: aggregate.crateToken,
+            crateMint: crate.mint,
+            depositor,
+            mintDestination: mintDestination.address,
+            // fee destinations are only checked if there is an issue fee
+            authorFeeDestination:
+              crate.issueFeeBps === 0
+                ? mintDestination.address
+                : aggregate.authorFeeDestination,
+            protocolFeeDestination:
+              crate.issueFeeBps === 0
+                ? mintDestination.address
+                : aggregate.protocolFeeDestination,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            crateTokenProgram: CRATE_ADDRESSES.CrateToken,
+          },
+          remainingAccounts: [
+            ...(await this.getSyncAccounts(aggregate)),
+            ...poolAccounts,
+            ...(await this.getReferrerAccounts(aggregateKey, referrer)),
+            ...(await this.getPositionAccounts(
+              aggregateKey,
+              depositor,
+              updatePosition
+            )),
+          ],
+        }
+      ),
+    ]);
+  }
+
+  /**
+   * Deposits SOL into a stake pool, then mints aSOL for the received stake pool tokens.
+   * The stake pool must be the one furthest below its max weight, otherwise the deposit is rejected.
+   * @returns
+   */
+  async depositSol({
+    aggregateKey,
+    mint,
+    lamports,
+    minMintAmount = new u64(0),
+    depositor = this.provider.wallet.publicKey,
+    referrer,
+    updatePosition = false,
+  }: {
+    aggregateKey: PublicKey;
+    /**
+     * Mint of the Marinade or Lido stake pool to deposit into.
+     */
+    mint: PublicKey;
+    lamports: u64;
+    /**
+     * Minimum amount of aSOL to receive. The transaction fails if less would be minted.
+     */
+    minMintAmount?: u64;
+    depositor?: PublicKey;
+    /**
+     * Owner of the referrer of the deposit, if any.
+     */
+    referrer?: PublicKey;
+    /**
+     * If true, records the deposit on the depositor's position, which must already exist.
+     */
+    updatePosition?: boolean;
+  }): Promise<TransactionEnvelope> {
+    const aggregate = (await this.program.account.aggregate.fetchNullable(
+      aggregateKey
+    )) as AggregateData;
+    if (!aggregate) {
+      throw new Error("No aggregate found.");
+    }
+    const pool = aggregate.stakePools.find((pool) => pool.mint.equals(mint));
+    if (!pool) {
+      throw new Error("Invalid mint.");
+    }
+
+    const { accounts, createAccountInstructions } =
+      await this.getMintASolAccounts({
+        aggregateKey,
+        aggregate,
+        mint,
+        depositor,
+      });
+
+    return new TransactionEnvelope(this.provider, [
+      ...(createAccountInstructions.input
+        ? [createAccountInstructions.input]
+        : []),
+      ...(createAccountInstructions.crate
+        ? [createAccountInstructions.crate]
+        : []),
+      this.program.instruction.depositSol(
+        lamports,
+        minMintAmount,
+        { referrer: !!referrer, position: updatePosition },
+        {
+          accounts: {
+            mintAsol: accounts,
+            systemProgram: SystemProgram.programId,
+          },
+          remainingAccounts: [
+            ...(await this.getSyncAccounts(aggregate)),
+            ...(await this.getDepositSolAccounts(pool)),
+            ...(await this.getReferrerAccounts(aggregateKey, referrer)),
+            ...(await this.getPositionAccounts(
+              aggregateKey,
+              depositor,
+              updatePosition
+            )),
+          ],
+        }
+      ),
+    ]);
+  }
+
+  /**
+   * Gets the remaining accounts to deposit SOL into a Marinade or Lido stake pool.
+   */
+  async getDepositSolAccounts(pool: StakePoolMeta): Promise<AccountMeta[]> {
+    if ("marinade" in pool.accountingMethod) {
+      return [
+        { pubkey: MARINADE_PROGRAM_ID, isSigner: false, isWritable: false },
+        { pubkey: pool.mint, isSigner: false, isWritable: true },
+        {
+          pubkey: (await generateMarinadeAddress("liq_sol", pool.poolState))[0],
+          isSigner: false,
+          isWritable: true,
+        },
+        {
+          pubkey: MARINADE_LIQ_POOL_MSOL_LEG,
+          isSigner: false,
+          isWritable: true,
+        },
+        {
+          pubkey: (
+            await generateMarinadeAddress(
+              "liq_st_sol_authority",
+              pool.poolState
+            )
+          )[0],
+          isSigner: false,
+          isWritable: false,
+        },
+        {
+          pubkey: (await generateMarinadeAddress("reserve", pool.poolState))[0],
+          isSigner: false,
+          isWritable: true,
+        },
+        {
+          pubkey: (await generateMarinadeAddress("st_mint", pool.poolState))[0],
+          isSigner: false,
+          isWritable: false,
+        },
+      ];
+    }
+    if ("lido" in pool.accountingMethod) {
+      return [
+        { pubkey: LIDO_PROGRAM_ID, isSigner: false, isWritable: false },
+        { pubkey: pool.mint, isSigner: false, isWritable: true },
+        {
+          pubkey: (
+            await generateLidoAddress("reserve_account", pool.poolState)
+          )[0],
+          isSigner: false,
+          isWritable: true,
+        },
+        {
+          pubkey: (
+            await generateLidoAddress("mint_authority", pool.poolState)
+          )[0],
+          isSigner: false,
+          isWritable: false,
+        },
+      ];
+    }
+    throw new Error("Stake pool does not accept SOL deposits.");
+  }
+
+  /**
+   * Deposits a stake account into a Marinade or SPL stake pool, then mints aSOL for the received stake pool tokens.
+   * @returns
+   */
+  async depositStakeAccount({
+    aggregateKey,
+    mint,
+    stakeAccount,
+    poolAccounts,
+    validatorIndex = 0,
+    minMintAmount = new u64(0),
+    depositor = this.provider.wallet.publicKey,
+    referrer,
+    updatePosition = false,
+  }: {
+    aggregateKey: PublicKey;
+    /**
+     * Mint of the stake pool to deposit into.
+     */
+    mint: PublicKey;
+    /**
+     * The stake account to deposit. Its staker and withdrawer must be the depositor.
+     */
+    stakeAccount: PublicKey;
+    /**
+     * The DepositStakeMarinade or DepositStakeSplStakePool accounts of the stake pool.
+     */
+    poolAccounts: AccountMeta[];
+    /**
+     * Index of the stake account's validator in the Marinade validator list. Ignored for SPL stake pools.
+     */
+    validatorIndex?: number;
+    /**
+     * Minimum amount of aSOL to receive. The transaction fails if less would be minted.
+     */
+    minMintAmount?: u64;
+    depositor?: PublicKey;
+    /**
+     * Owner of the referrer of the deposit, if any.
+     */
+    referrer?: PublicKey;
+    /**
+     * If true, records the deposit on the depositor's position, which must already exist.
+     */
+    updatePosition?: boolean;
+  }): Promise<TransactionEnvelope> {
+    const aggregate = (await this.program.account.aggregate.fetchNullable(
+      aggregateKey
+    )) as AggregateData;
+    if (!aggregate) {
+      throw new Error("No aggregate found.");
+    }
+
+    const { accounts, createAccountInstructions } =
+      await this.getMintASolAccounts({
+        aggregateKey,
+        aggregate,
+        mint,
+        depositor,
+      });
+
+    return new TransactionEnvelope(this.provider, [
+      ...(createAccountInstructions.input
+        ? [createAccountInstructions.input]
+        : []),
+      ...(createAccountInstructions.crate
+        ? [createAccountInstructions.crate]
+        : []),
+      this.program.instruction.depositStakeAccount(
+        validatorIndex,
+        minMintAmount,
+        { referrer: !!referrer, position: updatePosition },
+        {
+          accounts: {
+            mintAsol: accounts,
+            stakeAccount,
+            clock: SYSVAR_CLOCK_PUBKEY,
+            rent: SYSVAR_RENT_PUBKEY,
+            stakeHistory: SYSVAR_STAKE_HISTORY_PUBKEY,
+            systemProgram: SystemProgram.programId,
+            stakeProgram: StakeProgram.programId,
+          },
+          remainingAccounts: [
+            ...(await this.getSyncAccounts(aggregate)),
+            ...poolAccounts,
+            ...(await this.getReferrerAccounts(aggregateKey, referrer)),
+            ...(await this.getPositionAccounts(
+              aggregateKey,
+              depositor,
+              updatePosition
+            )),
+          ],
+        }
+      ),
+    ]);
+  }
+
+  /**
+   * Gets the remaining accounts used to synchronize the stake pools of an aggregate.
+   *
+   * These are a (pool state, Crate reserves) pair per stake pool, in the order of the aggregate's stake pools.
+   */
+  async getSyncAccounts(aggregate: AggregateData): Promise<AccountMeta[]> {
+    const accounts: AccountMeta[] = [];
+    for (const pool of aggregate.stakePools) {
+      accounts.push(
+        { pubkey: pool.poolState, isSigner: false, isWritable: false },
+        {
+          pubkey: await getATAAddress({
+            mint: pool.mint,
+            owner: aggregate.crateToken,
+          }),
+          isSigner: false,
+          isWritable: false,
+        }
+      );
+    }
+    return accounts;
+  }
+
+  /**
+   * Records a fresh snapshot on the aggregate and all of its stake pools.
+   * Anyone may call this. Stake pools with a stale exchange rate are skipped,
+   * and the aggregate snapshot is only recorded once every stake pool is fresh.
+   */
+  async refresh({
+    aggregateKey,
+  }: {
+    aggregateKey: PublicKey;
+  }): Promise<TransactionEnvelope> {
+    const aggregate = (await this.program.account.aggregate.fetchNullable(
+      aggregateKey
+    )) as AggregateData;
+    if (!aggregate) {
+      throw new Error("No aggregate found.");
+    }
+    const crate = await this.crate.fetchCrateTokenData(aggregate.crateToken);
+    if (!crate) {
+      throw new Error("No crate found.");
+    }
+
+    const stakePoolAccounts: AccountMeta[] = [];
+    for (const { mint } of aggregate.stakePools) {
+      const [stakePool] = await generateStakePoolAddress(aggregateKey, mint);
+      stakePoolAccounts.push({
+        pubkey: stakePool,
+        isSigner: false,
+        isWritable: true,
+      });
+    }
+
+    return new TransactionEnvelope(this.provider, [
+      this.program.instruction.refresh({
+        accounts: {
+          aggregate: aggregateKey,
+          snapshotHistory: (
+            await generateSnapshotHistoryAddress(aggregateKey)
+          )[0],
+          crateToken: aggregate.crateToken,
+          crateMint: crate.mint,
+        },
+        remainingAccounts: [
+          ...(await this.getSyncAccounts(aggregate)),
+          ...stakePoolAccounts,
+        ],
+      }),
+    ]);
+  }
+
+  /**
+   * Creates the exchange rate oracle of an Aggregate.
+   * @returns
+   */
+  async newExchangeRateOracle({
+    aggregate,
+    payer = this.provider.wallet.publicKey,
+  }: {
+    aggregate: PublicKey;
+    payer?: PublicKey;
+  }): Promise<{ tx: TransactionEnvelope; exchangeRateOracleKey: PublicKey }> {
+    const [exchangeRateOracle, bump] = await generateExchangeRateOracleAddress(
+      aggregate,
+      this.program.programId
+    );
+    const tx = new TransactionEnvelope(this.provider, [
+      this.program.instruction.newExchangeRateOracle(bump, {
+        accounts: {
+          aggregate,
+          exchangeRateOracle,
+          payer,
+          systemProgram: SystemProgram.programId,
+        },
+      }),
+    ]);
+    return { tx, exchangeRateOracleKey: exchangeRateOracle };
+  }
+
+  /**
+   * Writes the current exchange rate of an Aggregate to its exchange rate oracle.
+   * Anyone may call this.
+   */
+  async getExchangeRate({
+    aggregateKey,
+  }: {
+    aggregateKey: PublicKey;
+  }): Promise<TransactionEnvelope> {
+    const aggregate = (await this.program.account.aggregate.fetchNullable(
+      aggregateKey
+    )) as AggregateData;
+    if (!aggregate) {
+      throw new Error("No aggregate found.");
+    }
+    const crate = await this.crate.fetchCrateTokenData(aggregate.crateToken);
+    if (!crate) {
+      throw new Error("No crate found.");
+    }
+
+    return new TransactionEnvelope(this.provider, [
+      this.program.instruction.getExchangeRate({
+        accounts: {
+          aggregate: aggregateKey,
+          exchangeRateOracle: (
+            await generateExchangeRateOracleAddress(aggregateKey)
+          )[0],
+          crateToken: aggregate.crateToken,
+          crateMint: crate.mint,
+        },
+        remainingAccounts: await this.getSyncAccounts(aggregate),
+      }),
+    ]);
+  }
+
+  /**
+   * Redeems aSOL for a pro-rata share of the underlying stake pool tokens.
+   */
+  async redeem({
+    aggregateKey,
+    amount,
+    owner = this.provider.wallet.publicKey,
+    updatePosition = false,
+  }: {
+    aggregateKey: PublicKey;
+    amount: TokenAmount;
+    owner?: PublicKey;
+    /**
+     * If true, records the redemption on the owner's position, which must already exist.
+     */
+    updatePosition?: boolean;
+  }): Promise<TransactionEnvelope> {
+    const aggregate = (await this.program.account.aggregate.fetchNullable(
+      aggregateKey
+    )) as AggregateData;
+    if (!aggregate) {
+      throw new Error("No aggregate found.");
+    }
+
+    const instructions: TransactionInstruction[] = [];
+    const remainingAccounts: AccountMeta[] = [];
+    for (const { mint } of aggregate.stakePools) {
+      const [stakePool] = await generateStakePoolAddress(aggregateKey, mint);
+      const withdrawDestination = await getOrCreateATA({
+        provider: this.provider,
+        mint,
+        owner,
+      });
+      if (withdrawDestination.instruction) {
+        instructions.push(withdrawDestination.instruction);
+      }
+      remainingAccounts.push(
+        { pubkey: stakePool, isSigner: false, isWritable: true },
+        {
+          pubkey: await getATAAddress({ mint, owner: aggregate.crateToken }),
+          isSigner: false,
+          isWritable: true,
+        },
+        {
+          pubkey: withdrawDestination.address,
+          isSigner: false,
+          isWritable: true,
+        },
+        {
+          pubkey: await getATAAddress({ mint, owner: aggregateKey }),
+          isSigner: false,
+          isWritable: true,
+        },
+        {
+          pubkey: await getATAAddress({ mint, owner: CRATE_FEE_TO_ADDRESS }),
+          isSigner: false,
+          isWritable: true,
+        }
+      );
+    }
+
+    return new TransactionEnvelope(this.provider, [
+      ...instructions,
+      this.program.instruction.redeemAsol(amount.toU64(), {
+        accounts: {
+          redeemAsol: {
+            aggregate: aggregateKey,
+            snapshotHistory: (
+              await generateSnapshotHistoryAddress(aggregateKey)
+            )[0],
+            crateToken: aggregate.crateToken,
+            crateMint: amount.token.mintAccount,
+            redeemer: owner,
+            redeemerSource: await getATAAddress({
+              mint: amount.token.mintAccount,
+              owner,
+            }),
+            tokenProgram: TOKEN_PROGRAM_ID,
+            crateTokenProgram: CRATE_ADDRESSES.CrateToken,
+          },
+        },
+        remainingAccounts: [
+          ...(await this.getSyncAccounts(aggregate)),
+          ...remainingAccounts,
+          ...(await this.getPositionAccounts(
+            aggregateKey,
+            owner,
+            updatePosition
+          )),
+        ],
+      }),
+    ]);
+  }
+
+  /**
+   * Withdraws aSOL for the tokens of a single stake pool, paying the single withdraw fee.
+   */
+  async withdrawSingle({
+    aggregateKey,
+    amount,
+    mint,
+    owner = this.provider.wallet.publicKey,
+    updatePosition = false,
+  }: {
+    aggregateKey: PublicKey;
+    amount: TokenAmount;
+    /**
+     * Mint of the stake pool tokens to withdraw.
+     */
+    mint: PublicKey;
+    owner?: PublicKey;
+    /**
+     * If true, records the withdrawal on the owner's position, which must already exist.
+     */
+    updatePosition?: boolean;
+  }): Promise<TransactionEnvelope> {
+    const aggregate = (await this.program.account.aggregate.fetchNullable(
+      aggregateKey
+    )) as AggregateData;
+    if (!aggregate) {
+      throw new Error("No aggregate found.");
+    }
+
+    const [stakePool] = await generateStakePoolAddress(aggregateKey, mint);
+    const withdrawDestination = await getOrCreateATA({
+      provider: this.provider,
+      mint,
+      owner,
+    });
+
+    return new TransactionEnvelope(this.provider, [
+      ...(withdrawDestination.instruction
+        ? [withdrawDestination.instruction]
+        : []),
+      this.program.instruction.withdrawSingle(amount.toU64(), {
+        accounts: {
+          redeemAsol: {
+            aggregate: aggregateKey,
+            snapshotHistory: (
+              await generateSnapshotHistoryAddress(aggregateKey)
+            )[0],
+            crateToken: aggregate.crateToken,
+            crateMint: amount.token.mintAccount,
+            redeemer: owner,
+            redeemerSource: await getATAAddress({
+              mint: amount.token.mintAccount,
+              owner,
+            }),
+            tokenProgram: TOKEN_PROGRAM_ID,
+            crateTokenProgram: CRATE_ADDRESSES.CrateToken,
+          },
+          pool: {
+            stakePool,
+            crateUnderlying: await getATAAddress({
+              mint,
+              owner: aggregate.crateToken,
+            }),
+            withdrawDestination: withdrawDestination.address,
+            authorFeeDestination: await getATAAddress({
+              mint,
+              owner: aggregateKey,
+            }),
+            protocolFeeDestination: await getATAAddress({
+              mint,
+              owner: CRATE_FEE_TO_ADDRESS,
+            }),
+          },
+        },
+        remainingAccounts: [
+          ...(await this.getSyncAccounts(aggregate)),
+          ...(await this.getPositionAccounts(
+            aggregateKey,
+            owner,
+            updatePosition
+          )),
+        ],
+      }),
+    ]);
   }
 }
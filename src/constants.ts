@@ -8,10 +8,38 @@ export const ASOL_PROGRAM_ID = new PublicKey(
   "AURUqAcTZP8mhR6sWVxWyfBbpJRj4A3qqeFzLNhrwayE"
 );
 
+/**
+ * Owner of the Crate protocol fee token accounts.
+ */
+export const CRATE_FEE_TO_ADDRESS = new PublicKey(
+  "AAqAKWdsUPepSgXf7Msbp1pQ7yCPgYkBvXmNfTFBGAqp"
+);
+
+/**
+ * Program ID of the Marinade program.
+ */
+export const MARINADE_PROGRAM_ID = new PublicKey(
+  "MarBmsSgKXdrN1egZf5sqe1TMai9K1rChYNDJgjq7aD"
+);
+
 export const MARINADE_STATE_ACCOUNT = new PublicKey(
   "8szGkuLTAux9XMgZ2vtY39jVSowEcpBfFfD8hXSEqdGC"
 );
 
+/**
+ * mSOL leg of the Marinade liquidity pool.
+ */
+export const MARINADE_LIQ_POOL_MSOL_LEG = new PublicKey(
+  "7GgPYjS5Dza89wV6FpZ23kUJRG5vbQ1GM25ezspYFSoE"
+);
+
+/**
+ * Program ID of the Lido program.
+ */
+export const LIDO_PROGRAM_ID = new PublicKey(
+  "CrX7kMhLC3cSsXJdT7JDgqrRVWGnUpX3gfEfxxU2NVLi"
+);
+
 export const SOLIDO_ACCOUNT = new PublicKey(
   "49Yi1TKkNyYjPAFdR9LBvoHcUjuPX4Df5T5yv39w2XTn"
 );
@@ -1,7 +1,13 @@
 import { utils } from "@project-serum/anchor";
 import { PublicKey } from "@solana/web3.js";
 
-import { ASOL_PROGRAM_ID } from "./constants";
+import {
+  ASOL_PROGRAM_ID,
+  LIDO_PROGRAM_ID,
+  MARINADE_PROGRAM_ID,
+  MARINADE_STATE_ACCOUNT,
+  SOLIDO_ACCOUNT,
+} from "./constants";
 
 export const generateAggregateAddress = (
   crateToken: PublicKey,
@@ -27,3 +33,81 @@ export const generateStakePoolAddress = (
     programID
   );
 };
+
+export const generateSnapshotHistoryAddress = (
+  aggregate: PublicKey,
+  programID: PublicKey = ASOL_PROGRAM_ID
+): Promise<[PublicKey, number]> => {
+  return PublicKey.findProgramAddress(
+    [utils.bytes.utf8.encode("SnapshotHistory"), aggregate.toBuffer()],
+    programID
+  );
+};
+
+export const generateExchangeRateOracleAddress = (
+  aggregate: PublicKey,
+  programID: PublicKey = ASOL_PROGRAM_ID
+): Promise<[PublicKey, number]> => {
+  return PublicKey.findProgramAddress(
+    [utils.bytes.utf8.encode("ExchangeRateOracle"), aggregate.toBuffer()],
+    programID
+  );
+};
+
+export const generatePositionAddress = (
+  aggregate: PublicKey,
+  owner: PublicKey,
+  programID: PublicKey = ASOL_PROGRAM_ID
+): Promise<[PublicKey, number]> => {
+  return PublicKey.findProgramAddress(
+    [
+      utils.bytes.utf8.encode("Position"),
+      aggregate.toBuffer(),
+      owner.toBuffer(),
+    ],
+    programID
+  );
+};
+
+export const generateReferrerAddress = (
+  aggregate: PublicKey,
+  owner: PublicKey,
+  programID: PublicKey = ASOL_PROGRAM_ID
+): Promise<[PublicKey, number]> => {
+  return PublicKey.findProgramAddress(
+    [
+      utils.bytes.utf8.encode("Referrer"),
+      aggregate.toBuffer(),
+      owner.toBuffer(),
+    ],
+    programID
+  );
+};
+
+/**
+ * Generates an address derived from the Marinade state, e.g. its SOL reserve ("reserve").
+ */
+export const generateMarinadeAddress = (
+  seed: string,
+  state: PublicKey = MARINADE_STATE_ACCOUNT,
+  programID: PublicKey = MARINADE_PROGRAM_ID
+): Promise<[PublicKey, number]> => {
+  return PublicKey.findProgramAddress(
+    [state.toBuffer(), utils.bytes.utf8.encode(seed)],
+    programID
+  );
+};
+
+/**
+ * Generates an address derived from the Solido account, e.g. its SOL reserve ("reserve_account").
+ */
+export const generateLidoAddress = (
+  seed: string,
+  solido: PublicKey = SOLIDO_ACCOUNT,
+  programID: PublicKey = LIDO_PROGRAM_ID
+): Promise<[PublicKey, number]> => {
+  return PublicKey.findProgramAddress(
+    [solido.toBuffer(), utils.bytes.utf8.encode(seed)],
+    programID
+  );
+};
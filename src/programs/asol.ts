@@ -9,6 +9,10 @@ type ASolTypes = AnchorTypes<
   {
     aggregate: AggregateData;
     stakePool: StakePoolData;
+    snapshotHistory: SnapshotHistoryData;
+    exchangeRateOracle: ExchangeRateOracleData;
+    position: PositionData;
+    referrer: ReferrerData;
   },
   {
     AccountingMethod: AccountingMethod;
@@ -17,6 +21,10 @@ type ASolTypes = AnchorTypes<
     StakePoolStateSnapshot: StakePoolStateSnapshot;
     StakePoolStats: StakePoolStats;
     Snapshot: Snapshot;
+    SnapshotHistoryEntry: SnapshotHistoryEntry;
+    PoolExchangeRate: PoolExchangeRate;
+    MintDeposit: MintDeposit;
+    MintOptions: MintOptions;
     SOL: SOLValue;
     ASOL: ASOLValue;
   }
@@ -24,6 +32,11 @@ type ASolTypes = AnchorTypes<
 
 export type AggregateData = ASolTypes["Accounts"]["Aggregate"];
 export type StakePoolData = ASolTypes["Accounts"]["StakePool"];
+export type SnapshotHistoryData = ASolTypes["Accounts"]["SnapshotHistory"];
+export type ExchangeRateOracleData =
+  ASolTypes["Accounts"]["ExchangeRateOracle"];
+export type PositionData = ASolTypes["Accounts"]["Position"];
+export type ReferrerData = ASolTypes["Accounts"]["Referrer"];
 
 export type AccountingMethod =
   typeof AccountingMethods[keyof typeof AccountingMethods];
@@ -35,6 +48,9 @@ export const AccountingMethods = {
   Lido: {
     lido: {},
   },
+  SplStakePool: {
+    splStakePool: {},
+  },
 } as const;
 
 export type SOLValue = ASolTypes["Defined"]["SOL"];
@@ -46,13 +62,56 @@ export type StakePoolStateSnapshot =
   ASolTypes["Defined"]["StakePoolStateSnapshot"];
 export type StakePoolStats = ASolTypes["Defined"]["StakePoolStats"];
 export type Snapshot = ASolTypes["Defined"]["Snapshot"];
+export type SnapshotHistoryEntry =
+  ASolTypes["Defined"]["SnapshotHistoryEntry"];
+export type PoolExchangeRate = ASolTypes["Defined"]["PoolExchangeRate"];
+export type MintDeposit = ASolTypes["Defined"]["MintDeposit"];
+export type MintOptions = ASolTypes["Defined"]["MintOptions"];
 
 export type ASolProgram = ASolTypes["Program"];
 
 export type NewAggregateEvent = ASolTypes["Events"]["NewAggregateEvent"];
+export type NewSnapshotHistoryEvent =
+  ASolTypes["Events"]["NewSnapshotHistoryEvent"];
+export type NewPositionEvent = ASolTypes["Events"]["NewPositionEvent"];
+export type NewReferrerEvent = ASolTypes["Events"]["NewReferrerEvent"];
+export type NewExchangeRateOracleEvent =
+  ASolTypes["Events"]["NewExchangeRateOracleEvent"];
 export type AddStakePoolEvent = ASolTypes["Events"]["AddStakePoolEvent"];
+export type RetireStakePoolEvent = ASolTypes["Events"]["RetireStakePoolEvent"];
+export type RemoveStakePoolEvent = ASolTypes["Events"]["RemoveStakePoolEvent"];
+export type SetStakePoolLimitsEvent =
+  ASolTypes["Events"]["SetStakePoolLimitsEvent"];
+export type SetStakePoolPriceBandEvent =
+  ASolTypes["Events"]["SetStakePoolPriceBandEvent"];
 export type SetCuratorEvent = ASolTypes["Events"]["SetCuratorEvent"];
+export type SetPendingCuratorEvent =
+  ASolTypes["Events"]["SetPendingCuratorEvent"];
+export type SetCuratorSetterEvent =
+  ASolTypes["Events"]["SetCuratorSetterEvent"];
+export type SetPendingCuratorSetterEvent =
+  ASolTypes["Events"]["SetPendingCuratorSetterEvent"];
+export type SetGuardianEvent = ASolTypes["Events"]["SetGuardianEvent"];
+export type SetFeesEvent = ASolTypes["Events"]["SetFeesEvent"];
+export type SetFeeDestinationsEvent =
+  ASolTypes["Events"]["SetFeeDestinationsEvent"];
+export type SweepFeesEvent = ASolTypes["Events"]["SweepFeesEvent"];
+export type SetPerformanceFeeEvent =
+  ASolTypes["Events"]["SetPerformanceFeeEvent"];
+export type SetReferralFeeEvent = ASolTypes["Events"]["SetReferralFeeEvent"];
+export type PerformanceFeeEvent = ASolTypes["Events"]["PerformanceFeeEvent"];
+export type ClaimPerformanceFeeEvent =
+  ASolTypes["Events"]["ClaimPerformanceFeeEvent"];
+export type RefreshEvent = ASolTypes["Events"]["RefreshEvent"];
+export type ExchangeRateEvent = ASolTypes["Events"]["ExchangeRateEvent"];
+export type PauseEvent = ASolTypes["Events"]["PauseEvent"];
+export type UnpauseEvent = ASolTypes["Events"]["UnpauseEvent"];
 export type MintASolEvent = ASolTypes["Events"]["MintASolEvent"];
+export type MintMultiEvent = ASolTypes["Events"]["MintMultiEvent"];
+export type RedeemASolEvent = ASolTypes["Events"]["RedeemASolEvent"];
+export type WithdrawSingleEvent = ASolTypes["Events"]["WithdrawSingleEvent"];
+export type SetSingleWithdrawFeeEvent =
+  ASolTypes["Events"]["SetSingleWithdrawFeeEvent"];
 export type AggregateInfoEvent = ASolTypes["Events"]["AggregateInfoEvent"];
 
 export type SyncAndMintAccounts =
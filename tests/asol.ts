@@ -1,17 +1,44 @@
+import { generateCrateAddress } from "@crateprotocol/crate-sdk";
 import { EventParser } from "@project-serum/anchor";
 import { expectTX } from "@saberhq/chai-solana";
-import { createInitMintInstructions } from "@saberhq/token-utils";
-import type { PublicKey } from "@solana/web3.js";
-import { Keypair } from "@solana/web3.js";
+import { TransactionEnvelope } from "@saberhq/solana-contrib";
+import {
+  createInitMintInstructions,
+  getATAAddress,
+  getOrCreateATA,
+  getTokenAccount,
+  SPLToken,
+  Token,
+  TOKEN_PROGRAM_ID,
+  TokenAmount,
+  u64,
+} from "@saberhq/token-utils";
+import type { PublicKey, TransactionInstruction } from "@solana/web3.js";
+import {
+  Authorized,
+  Keypair,
+  LAMPORTS_PER_SOL,
+  StakeProgram,
+} from "@solana/web3.js";
 import { expect } from "chai";
 
 import type { AddStakePoolEvent } from "../src";
-import { AccountingMethods } from "../src";
+import {
+  AccountingMethods,
+  CRATE_FEE_TO_ADDRESS,
+  LAMPORTS_DECIMALS,
+  LIDO_STAKED_SOL,
+  MARINADE_STAKED_SOL,
+  MARINADE_STATE_ACCOUNT,
+  SOLIDO_ACCOUNT,
+  STAKE_POOL_TOKENS,
+} from "../src";
 import { makeSDK } from "./workspace";
 
 describe("aSOL", () => {
   const sdk = makeSDK();
   let aggregate: PublicKey;
+  let crateMint: PublicKey;
 
   beforeEach(async () => {
     const mintKP = Keypair.generate();
@@ -19,6 +46,7 @@ describe("aSOL", () => {
       mintKP,
     });
     aggregate = aggregateKey;
+    crateMint = mintKP.publicKey;
     await expectTX(createTX, "Create Crate Token").to.be.fulfilled;
   });
 
@@ -35,6 +63,7 @@ describe("aSOL", () => {
       aggregate,
       mint: lidoKP.publicKey,
       method: AccountingMethods.Lido,
+      poolState: SOLIDO_ACCOUNT,
     });
     const result = await tx.send();
     await expectTX(result).to.be.fulfilled;
@@ -53,5 +82,273 @@ describe("aSOL", () => {
     });
   });
 
-  // todo: figure out how to mock lido and marinade locally
+  it("sweep collected fees", async () => {
+    const feeMintKP = Keypair.generate();
+    const fakeFeeMint = await createInitMintInstructions({
+      provider: sdk.provider,
+      mintKP: feeMintKP,
+      decimals: 9,
+    });
+    await expectTX(fakeFeeMint).to.be.fulfilled;
+
+    // fees collected by the crate are paid to accounts owned by the aggregate
+    const feeSource = await getOrCreateATA({
+      provider: sdk.provider,
+      mint: feeMintKP.publicKey,
+      owner: aggregate,
+    });
+    const amount = new u64(1_000_000);
+    await expectTX(
+      new TransactionEnvelope(sdk.provider, [
+        ...(feeSource.instruction ? [feeSource.instruction] : []),
+        SPLToken.createMintToInstruction(
+          TOKEN_PROGRAM_ID,
+          feeMintKP.publicKey,
+          feeSource.address,
+          sdk.provider.wallet.publicKey,
+          [],
+          amount
+        ),
+      ]),
+      "Collect fees"
+    ).to.be.fulfilled;
+
+    const recipient = Keypair.generate().publicKey;
+    const sweepTX = await sdk.sweepFees({
+      aggregate,
+      mint: feeMintKP.publicKey,
+      recipient,
+    });
+    await expectTX(sweepTX, "Sweep fees").to.be.fulfilled;
+
+    const recipientTokens = await getTokenAccount(
+      sdk.provider,
+      await getATAAddress({ mint: feeMintKP.publicKey, owner: recipient })
+    );
+    expect(recipientTokens.amount.toString()).to.eq(amount.toString());
+    const sourceTokens = await getTokenAccount(
+      sdk.provider,
+      feeSource.address
+    );
+    expect(sourceTokens.amount.toString()).to.eq("0");
+  });
+
+  describe("stake pools", () => {
+    const owner = sdk.provider.wallet.publicKey;
+    const depositAmount = new u64(LAMPORTS_PER_SOL);
+
+    const balanceOf = async (mint: PublicKey): Promise<u64> => {
+      const address = await getATAAddress({ mint, owner });
+      if (!(await sdk.provider.connection.getAccountInfo(address))) {
+        return new u64(0);
+      }
+      return (await getTokenAccount(sdk.provider, address)).amount;
+    };
+
+    const asolAmount = (amount: u64): TokenAmount =>
+      new TokenAmount(Token.fromMint(crateMint, LAMPORTS_DECIMALS), amount);
+
+    // ties go to Marinade, after which Lido has the most headroom
+    const depositIntoBothPools = async (): Promise<void> => {
+      for (const mint of [MARINADE_STAKED_SOL, LIDO_STAKED_SOL]) {
+        const depositTX = await sdk.depositSol({
+          aggregateKey: aggregate,
+          mint,
+          lamports: depositAmount,
+        });
+        await expectTX(depositTX, "Deposit SOL").to.be.fulfilled;
+      }
+    };
+
+    before(async function () {
+      // loaded by ./scripts/test-e2e-stake-pools.sh
+      const [marinade, lido] = await Promise.all([
+        sdk.provider.connection.getAccountInfo(MARINADE_STATE_ACCOUNT),
+        sdk.provider.connection.getAccountInfo(SOLIDO_ACCOUNT),
+      ]);
+      if (!marinade || !lido) {
+        this.skip();
+      }
+    });
+
+    beforeEach(async () => {
+      const pools = [
+        {
+          mint: MARINADE_STAKED_SOL,
+          method: AccountingMethods.Marinade,
+          poolState: MARINADE_STATE_ACCOUNT,
+        },
+        {
+          mint: LIDO_STAKED_SOL,
+          method: AccountingMethods.Lido,
+          poolState: SOLIDO_ACCOUNT,
+        },
+      ];
+      for (const pool of pools) {
+        const { tx } = await sdk.addStakePool({ aggregate, ...pool });
+        await expectTX(tx, "Add stake pool").to.be.fulfilled;
+      }
+
+      // the crate reserves and the withdraw fee destinations of each stake pool
+      const [crateToken] = await generateCrateAddress(crateMint);
+      const instructions: TransactionInstruction[] = [];
+      for (const { mint } of pools) {
+        for (const ataOwner of [crateToken, aggregate, CRATE_FEE_TO_ADDRESS]) {
+          const { instruction } = await getOrCreateATA({
+            provider: sdk.provider,
+            mint,
+            owner: ataOwner,
+          });
+          if (instruction) {
+            instructions.push(instruction);
+          }
+        }
+      }
+      await expectTX(
+        new TransactionEnvelope(sdk.provider, instructions),
+        "Create token accounts"
+      ).to.be.fulfilled;
+    });
+
+    it("deposit SOL into the pool with the most headroom", async () => {
+      const depositMarinade = await sdk.depositSol({
+        aggregateKey: aggregate,
+        mint: MARINADE_STAKED_SOL,
+        lamports: depositAmount,
+      });
+      await expectTX(depositMarinade, "Deposit SOL into Marinade").to.be
+        .fulfilled;
+      expect((await balanceOf(crateMint)).toString()).to.not.eq("0");
+
+      // Marinade now has less headroom than Lido
+      const depositMarinadeAgain = await sdk.depositSol({
+        aggregateKey: aggregate,
+        mint: MARINADE_STAKED_SOL,
+        lamports: depositAmount,
+      });
+      await expectTX(depositMarinadeAgain, "Deposit SOL into Marinade again")
+        .to.be.rejected;
+
+      const depositLido = await sdk.depositSol({
+        aggregateKey: aggregate,
+        mint: LIDO_STAKED_SOL,
+        lamports: depositAmount,
+      });
+      await expectTX(depositLido, "Deposit SOL into Lido").to.be.fulfilled;
+
+      const [crateToken] = await generateCrateAddress(crateMint);
+      for (const mint of [MARINADE_STAKED_SOL, LIDO_STAKED_SOL]) {
+        const reserves = await getTokenAccount(
+          sdk.provider,
+          await getATAAddress({ mint, owner: crateToken })
+        );
+        expect(reserves.amount.toString()).to.not.eq("0");
+      }
+    });
+
+    it("redeem aSOL for every stake pool", async () => {
+      await depositIntoBothPools();
+      const msolBefore = await balanceOf(MARINADE_STAKED_SOL);
+      const stsolBefore = await balanceOf(LIDO_STAKED_SOL);
+
+      const redeemTX = await sdk.redeem({
+        aggregateKey: aggregate,
+        amount: asolAmount(await balanceOf(crateMint)),
+      });
+      await expectTX(redeemTX, "Redeem aSOL").to.be.fulfilled;
+
+      expect((await balanceOf(crateMint)).toString()).to.eq("0");
+      expect((await balanceOf(MARINADE_STAKED_SOL)).gt(msolBefore)).to.eq(
+        true
+      );
+      expect((await balanceOf(LIDO_STAKED_SOL)).gt(stsolBefore)).to.eq(true);
+    });
+
+    it("withdraw aSOL for a single stake pool", async () => {
+      await depositIntoBothPools();
+      const asolBefore = await balanceOf(crateMint);
+      const msolBefore = await balanceOf(MARINADE_STAKED_SOL);
+      const stsolBefore = await balanceOf(LIDO_STAKED_SOL);
+
+      const withdrawAmount = asolBefore.divn(4);
+      const withdrawTX = await sdk.withdrawSingle({
+        aggregateKey: aggregate,
+        amount: asolAmount(withdrawAmount),
+        mint: MARINADE_STAKED_SOL,
+      });
+      await expectTX(withdrawTX, "Withdraw single").to.be.fulfilled;
+
+      expect((await balanceOf(crateMint)).toString()).to.eq(
+        asolBefore.sub(withdrawAmount).toString()
+      );
+      expect((await balanceOf(MARINADE_STAKED_SOL)).gt(msolBefore)).to.eq(
+        true
+      );
+      expect((await balanceOf(LIDO_STAKED_SOL)).toString()).to.eq(
+        stsolBefore.toString()
+      );
+    });
+
+    it("mint aSOL from several stake pools", async () => {
+      await depositIntoBothPools();
+      const redeemTX = await sdk.redeem({
+        aggregateKey: aggregate,
+        amount: asolAmount(await balanceOf(crateMint)),
+      });
+      await expectTX(redeemTX, "Redeem aSOL").to.be.fulfilled;
+
+      const msolBefore = await balanceOf(MARINADE_STAKED_SOL);
+      const stsolBefore = await balanceOf(LIDO_STAKED_SOL);
+      const msolAmount = msolBefore.divn(2);
+      const stsolAmount = stsolBefore.divn(2);
+
+      const mintTX = await sdk.mintMulti({
+        aggregateKey: aggregate,
+        amounts: [
+          new TokenAmount(STAKE_POOL_TOKENS.MARINADE, msolAmount),
+          new TokenAmount(STAKE_POOL_TOKENS.LIDO, stsolAmount),
+        ],
+      });
+      await expectTX(mintTX, "Mint multi").to.be.fulfilled;
+
+      expect((await balanceOf(crateMint)).toString()).to.not.eq("0");
+      expect((await balanceOf(MARINADE_STAKED_SOL)).toString()).to.eq(
+        msolBefore.sub(msolAmount).toString()
+      );
+      expect((await balanceOf(LIDO_STAKED_SOL)).toString()).to.eq(
+        stsolBefore.sub(stsolAmount).toString()
+      );
+    });
+
+    // Marinade only accepts active stake delegated to a validator on its list,
+    // which the local validator cannot provide.
+    it("reject stake account deposits into Lido", async () => {
+      const stakeAccountKP = Keypair.generate();
+      const rent =
+        await sdk.provider.connection.getMinimumBalanceForRentExemption(
+          StakeProgram.space
+        );
+      const createStakeAccount = new TransactionEnvelope(
+        sdk.provider,
+        StakeProgram.createAccount({
+          fromPubkey: owner,
+          stakePubkey: stakeAccountKP.publicKey,
+          authorized: new Authorized(owner, owner),
+          lamports: rent + LAMPORTS_PER_SOL,
+        }).instructions,
+        [stakeAccountKP]
+      );
+      await expectTX(createStakeAccount, "Create stake account").to.be
+        .fulfilled;
+
+      const depositTX = await sdk.depositStakeAccount({
+        aggregateKey: aggregate,
+        mint: LIDO_STAKED_SOL,
+        stakeAccount: stakeAccountKP.publicKey,
+        poolAccounts: [],
+      });
+      await expectTX(depositTX, "Deposit stake account into Lido").to.be
+        .rejected;
+    });
+  });
 });